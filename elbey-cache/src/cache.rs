@@ -7,11 +7,17 @@ use iced::widget::svg::Handle as SvgHandle;
 use serde::{Deserialize, Serialize};
 use sled::{Batch, Config, Db, IVec};
 
+use crate::{executables, hidden, history, man_pages, pins, usage};
 use crate::{
-    preserve_icon_handles, AppDescriptor, IconHandle, DEFAULT_ICON_SIZE, FALLBACK_ICON_HANDLE,
+    preserve_icon_handles, AppDescriptor, IconHandle, ManPage, DEFAULT_ICON_SIZE,
+    FALLBACK_ICON_HANDLE,
 };
 
 const CACHE_NAMESPACE: &str = "elbey";
+/// Trees of choices the user made, copied into a new cache from the previous version's cache
+const CARRIED_TREES: &[&str] = &[pins::PINS_TREE, hidden::HIDDEN_TREE];
+/// The mode whose usage counts rank executables
+const RUN_MODE: &str = "run";
 
 static SCAN_KEY: [u8; 4] = 0_i32.to_be_bytes();
 
@@ -127,6 +133,23 @@ fn populate_icon_data(entry: &mut CachedAppDescriptor) -> bool {
     entry.icon_data.is_some()
 }
 
+/// Load an icon given by name, resolved in the icon theme, or by absolute path.  Icons that
+/// cannot be found or decoded are `NotLoaded`, so the fallback icon is rendered.
+pub fn load_icon(icon: &str) -> IconHandle {
+    let path = if Path::new(icon).is_absolute() {
+        Some(PathBuf::from(icon))
+    } else {
+        lookup(icon)
+            .with_size(DEFAULT_ICON_SIZE)
+            .with_cache()
+            .find()
+    };
+
+    path.and_then(|path| icon_data_from_path(&path))
+        .map(|icon_data| icon_handle_from_data(&icon_data))
+        .unwrap_or(IconHandle::NotLoaded)
+}
+
 impl CachedAppDescriptor {
     fn normalize(mut self) -> Self {
         if self.lower_title.is_empty() {
@@ -194,12 +217,22 @@ impl Cache {
     /// Create a cache using the default Elbey cache namespace.
     pub fn new(apps_loader: fn() -> Vec<AppDescriptor>) -> Self {
         let path = resolve_db_file_path();
-        let config = Config::new().path(path);
+        let config = Config::new().path(&path);
         let db = config.open().unwrap();
+        if !db.was_recovered() {
+            if let Err(err) = carry_over_user_data(&db, &path) {
+                eprintln!("Failed to copy settings from the previous cache: {err}");
+            }
+        }
 
         Cache { apps_loader, db }
     }
 
+    /// Returns true if the cache holds no entries.
+    pub fn is_empty(&self) -> bool {
+        self.db.is_empty()
+    }
+
     /// Load all cached entries into app descriptors, if available.
     fn read_all(&mut self) -> Option<Vec<AppDescriptor>> {
         let entries = self.read_cached_entries()?;
@@ -380,6 +413,128 @@ impl Cache {
         self.update_from_loader(Some(selected_app.appid.as_str()))
     }
 
+    /// Patch the cache with `updated` apps and drop the `removed` app ids, keeping usage counts
+    /// and reusing icon data when an app's icon is unchanged.  Returns the updated apps as stored.
+    pub fn apply_changes(
+        &mut self,
+        updated: Vec<AppDescriptor>,
+        removed: &[String],
+    ) -> anyhow::Result<Vec<AppDescriptor>> {
+        let mut cached_by_id: HashMap<String, CachedAppDescriptor> = self
+            .read_cached_entries()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|entry| (entry.appid.clone(), entry))
+            .collect();
+
+        for appid in removed {
+            cached_by_id.remove(appid);
+        }
+
+        let mut updated_ids = Vec::with_capacity(updated.len());
+        for mut app in updated {
+            let cached_icon = match cached_by_id.remove(&app.appid) {
+                Some(previous) => {
+                    app.exec_count = previous.exec_count;
+                    if previous.icon_name == app.icon_name {
+                        app.icon_path = previous.icon_path;
+                        previous.icon_data
+                    } else {
+                        None
+                    }
+                }
+                None => None,
+            };
+
+            let mut cached = CachedAppDescriptor::from_app_descriptor(app, cached_icon);
+            populate_icon_data(&mut cached);
+            updated_ids.push(cached.appid.clone());
+            cached_by_id.insert(cached.appid.clone(), cached);
+        }
+
+        let updated_apps = updated_ids
+            .iter()
+            .filter_map(|appid| cached_by_id.get(appid).cloned())
+            .map(CachedAppDescriptor::into_app_descriptor)
+            .collect();
+
+        self.write_snapshot(cached_by_id.into_values())?;
+        Ok(updated_apps)
+    }
+
+    /// Load the executables found in `dirs` for run mode, ordered by run mode usage and then name.
+    pub fn load_executables(&mut self, dirs: &[PathBuf]) -> anyhow::Result<Vec<AppDescriptor>> {
+        let counts = usage::counts(&self.db, RUN_MODE)?;
+        let mut commands: Vec<AppDescriptor> = executables::load_executables(&self.db, dirs)?
+            .into_iter()
+            .map(|name| AppDescriptor {
+                appid: name.clone(),
+                lower_title: name.to_lowercase(),
+                exec: Some(name.clone()),
+                exec_count: counts.get(&name).copied().unwrap_or(0),
+                icon_name: None,
+                icon_path: Some(PathBuf::new()),
+                icon_handle: FALLBACK_ICON_HANDLE.clone(),
+                title: name,
+            })
+            .collect();
+
+        commands.sort_by(|a, b| (b.exec_count, &a.title).cmp(&(a.exec_count, &b.title)));
+        Ok(commands)
+    }
+
+    /// Load the man pages in the man directories `dirs` for man mode, ordered by name.
+    pub fn load_man_pages(&mut self, dirs: &[PathBuf]) -> anyhow::Result<Vec<ManPage>> {
+        let mut pages = man_pages::load_man_pages(&self.db, dirs)?;
+        pages.sort_by(|a, b| (&a.name, &a.section).cmp(&(&b.name, &b.section)));
+        Ok(pages)
+    }
+
+    /// Increment the run mode usage count for the command `name`.
+    pub fn record_run(&mut self, name: &str) -> anyhow::Result<()> {
+        usage::record(&self.db, RUN_MODE, name)
+    }
+
+    /// Usage counts of the items picked in `mode`, keyed by item.
+    pub fn usage_counts(&self, mode: &str) -> anyhow::Result<HashMap<String, usize>> {
+        usage::counts(&self.db, mode)
+    }
+
+    /// Increment the usage count of `key` in `mode`.
+    pub fn record_usage(&mut self, mode: &str, key: &str) -> anyhow::Result<()> {
+        usage::record(&self.db, mode, key)
+    }
+
+    /// The text entered in `mode`, most recent first.
+    pub fn history(&self, mode: &str) -> anyhow::Result<Vec<String>> {
+        history::history(&self.db, mode)
+    }
+
+    /// Add `entry` to the front of the history of `mode`.
+    pub fn record_history(&mut self, mode: &str, entry: &str) -> anyhow::Result<()> {
+        history::record(&self.db, mode, entry)
+    }
+
+    /// The keys of the items pinned in `mode`, in the order the user chose.
+    pub fn pins(&self, mode: &str) -> anyhow::Result<Vec<String>> {
+        pins::pins(&self.db, mode)
+    }
+
+    /// Replace the items pinned in `mode`.
+    pub fn set_pins(&mut self, mode: &str, pins: &[String]) -> anyhow::Result<()> {
+        pins::set_pins(&self.db, mode, pins)
+    }
+
+    /// The ids of the apps the user hid.
+    pub fn hidden_apps(&self) -> anyhow::Result<Vec<String>> {
+        hidden::hidden_apps(&self.db)
+    }
+
+    /// Hide the app `appid` from the launcher, or show it again.
+    pub fn set_hidden(&mut self, appid: &str, hidden: bool) -> anyhow::Result<()> {
+        hidden::set_hidden(&self.db, appid, hidden)
+    }
+
     /// Store a snapshot of apps, reusing cached icon data when possible.
     pub fn save_snapshot(&mut self, apps: &[AppDescriptor]) -> anyhow::Result<()> {
         let cached_icons: HashMap<String, Option<CachedIcon>> = self
@@ -459,6 +614,43 @@ fn resolve_db_file_path() -> PathBuf {
     path
 }
 
+/// The cache directory of the most recently used other version of the cache, if any.
+fn previous_db_path(current: &Path) -> Option<PathBuf> {
+    let prefix = format!("{CACHE_NAMESPACE}-");
+    std::fs::read_dir(current.parent()?)
+        .ok()?
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            let name = entry.file_name();
+            let is_version = name
+                .to_string_lossy()
+                .strip_prefix(&prefix)
+                .is_some_and(|version| version.starts_with(|c: char| c.is_ascii_digit()));
+            is_version && entry.path() != current && entry.path().is_dir()
+        })
+        .max_by_key(|entry| entry.metadata().and_then(|meta| meta.modified()).ok())
+        .map(|entry| entry.path())
+}
+
+/// Copy the user's choices, such as pins, from the previous version's cache into a new one, so
+/// they outlive cache format changes.
+fn carry_over_user_data(db: &Db, path: &Path) -> anyhow::Result<()> {
+    let Some(previous) = previous_db_path(path) else {
+        return Ok(());
+    };
+    let previous = Config::new().path(previous).open()?;
+    for name in CARRIED_TREES {
+        let from = previous.open_tree(name)?;
+        let to = db.open_tree(name)?;
+        for entry in from.iter() {
+            let (key, value) = entry?;
+            to.insert(key, value)?;
+        }
+        to.flush()?;
+    }
+    Ok(())
+}
+
 /// Remove the cache directory for the default namespace.
 pub fn clear_cache_dir() -> std::io::Result<()> {
     let path = resolve_db_file_path();
@@ -518,7 +710,7 @@ mod tests {
             appid: appid.to_string(),
             title: title.to_string(),
             lower_title: title.to_lowercase(),
-            exec: "/bin/true".to_string(),
+            exec: Some("/bin/true".to_string()),
             exec_count,
             icon_name: None,
             icon_path,
@@ -549,7 +741,7 @@ mod tests {
             appid: "test-app".to_string(),
             title: "Test App".to_string(),
             lower_title: "test app".to_string(),
-            exec: "/bin/true".to_string(),
+            exec: Some("/bin/true".to_string()),
             exec_count: 0,
             icon_name: None,
             icon_path: Some(icon_path),
@@ -600,7 +792,7 @@ mod tests {
         let mut cache = Cache::new(shared_loader);
         let initial_app = make_app("app-1", "Cached App", 3, Some(icon_path.clone()));
         cache
-            .build_snapshot_with_icons(&[initial_app.clone()])
+            .build_snapshot_with_icons(std::slice::from_ref(&initial_app))
             .expect("seed cache");
 
         let refreshed_app = AppDescriptor {
@@ -639,11 +831,11 @@ mod tests {
         let mut cache = Cache::new(shared_loader);
         let initial_app = make_app("app-1", "Cached App", 0, Some(icon_path.clone()));
         cache
-            .build_snapshot_with_icons(&[initial_app.clone()])
+            .build_snapshot_with_icons(std::slice::from_ref(&initial_app))
             .expect("seed cache");
 
-        let apps_before = cache.read_all().expect("read initial snapshot");
-        let initial_handle = apps_before[0].icon_handle.clone();
+        let mut apps = cache.read_all().expect("read initial snapshot");
+        let initial_handle = apps[0].icon_handle.clone();
 
         let refreshed_app = AppDescriptor {
             icon_path: None,
@@ -652,10 +844,9 @@ mod tests {
         };
         *LOADER_APPS.lock().expect("lock loader apps") = vec![refreshed_app];
 
-        cache.refresh().expect("refresh cache");
-        let apps_after = cache.read_all().expect("read refreshed snapshot");
+        cache.refresh_in_place(&mut apps).expect("refresh cache");
 
-        assert_eq!(apps_after[0].icon_handle, initial_handle);
+        assert_eq!(apps[0].icon_handle, initial_handle);
     }
 
     #[test]
@@ -684,7 +875,7 @@ mod tests {
             appid: "legacy-app".to_string(),
             title: "Legacy App".to_string(),
             lower_title: String::new(),
-            exec: "/bin/true".to_string(),
+            exec: Some("/bin/true".to_string()),
             exec_count: 1,
             icon_name: None,
             icon_path: None,
@@ -715,4 +906,172 @@ mod tests {
         assert_eq!(apps[0].appid, "app-1");
         assert!(!cache.is_empty());
     }
+
+    #[test]
+    fn test_apply_changes_patches_entries() {
+        let _guard = prepare_test_cache();
+        let mut cache = Cache::new(empty_loader);
+        let apps = vec![
+            make_app("app-1", "Keep", 4, None),
+            make_app("app-2", "Drop", 2, None),
+        ];
+        cache.save_snapshot(&apps).expect("store snapshot");
+
+        let updated = cache
+            .apply_changes(
+                vec![
+                    make_app("app-1", "Renamed", 0, None),
+                    make_app("app-3", "New", 0, None),
+                ],
+                &["app-2".to_string()],
+            )
+            .expect("apply changes");
+        assert_eq!(updated.len(), 2);
+
+        let apps = cache.read_all().expect("read snapshot");
+        let summary: Vec<(&str, &str, usize)> = apps
+            .iter()
+            .map(|app| (app.appid.as_str(), app.title.as_str(), app.exec_count))
+            .collect();
+        assert_eq!(summary, vec![("app-1", "Renamed", 4), ("app-3", "New", 0)]);
+    }
+
+    #[test]
+    fn test_load_executables_ranks_by_run_usage() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let _guard = prepare_test_cache();
+        let bin_dir = set_test_cache_home().join("test-bin");
+        let _ = std::fs::remove_dir_all(&bin_dir);
+        std::fs::create_dir_all(&bin_dir).expect("create bin dir");
+        for name in ["alpha", "beta", "not-executable"] {
+            let path = bin_dir.join(name);
+            std::fs::write(&path, "#!/bin/sh\n").expect("write executable");
+            let mode = if name == "not-executable" {
+                0o644
+            } else {
+                0o755
+            };
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode))
+                .expect("set permissions");
+        }
+
+        let mut cache = Cache::new(empty_loader);
+        cache.record_run("beta").expect("record run");
+        let commands = cache
+            .load_executables(std::slice::from_ref(&bin_dir))
+            .expect("load executables");
+
+        let names: Vec<(&str, usize)> = commands
+            .iter()
+            .map(|command| (command.title.as_str(), command.exec_count))
+            .collect();
+        assert_eq!(names, vec![("beta", 1), ("alpha", 0)]);
+        assert!(cache.read_all().expect("read snapshot").is_empty());
+    }
+
+    #[test]
+    fn test_load_man_pages_rescans_changed_sections() {
+        use std::io::Write;
+
+        let _guard = prepare_test_cache();
+        let man_dir = set_test_cache_home().join("test-man");
+        let _ = std::fs::remove_dir_all(&man_dir);
+        std::fs::create_dir_all(man_dir.join("man1")).expect("create man1");
+        std::fs::create_dir_all(man_dir.join("man5")).expect("create man5");
+        let mut encoder = flate2::write::GzEncoder::new(
+            std::fs::File::create(man_dir.join("man1/ls.1.gz")).expect("create page"),
+            flate2::Compression::default(),
+        );
+        encoder
+            .write_all(b".TH LS 1\n.SH NAME\nls \\- list directory contents\n")
+            .expect("write page");
+        encoder.finish().expect("finish page");
+        std::fs::write(
+            man_dir.join("man5/passwd.5"),
+            ".SH NAME\npasswd \\- the password file\n",
+        )
+        .expect("write page");
+
+        let mut cache = Cache::new(empty_loader);
+        let summary = |pages: Vec<ManPage>| -> Vec<(String, String, Option<String>)> {
+            pages
+                .into_iter()
+                .map(|page| (page.name, page.section, page.description))
+                .collect()
+        };
+        let dirs = std::slice::from_ref(&man_dir);
+        assert_eq!(
+            summary(cache.load_man_pages(dirs).expect("load man pages")),
+            vec![
+                (
+                    "ls".to_string(),
+                    "1".to_string(),
+                    Some("list directory contents".to_string())
+                ),
+                (
+                    "passwd".to_string(),
+                    "5".to_string(),
+                    Some("the password file".to_string())
+                ),
+            ]
+        );
+
+        std::fs::remove_file(man_dir.join("man5/passwd.5")).expect("remove page");
+        let pages = cache.load_man_pages(dirs).expect("load man pages");
+        assert_eq!(pages.len(), 1);
+    }
+
+    #[test]
+    fn test_pins_and_hidden_apps_carry_over_from_previous_version() {
+        let _guard = prepare_test_cache();
+        let previous = set_test_cache_home().join(format!("{CACHE_NAMESPACE}-0.0.1"));
+        let _ = std::fs::remove_dir_all(&previous);
+        {
+            let db = Config::new()
+                .path(&previous)
+                .open()
+                .expect("open old cache");
+            pins::set_pins(&db, "drun", &["b".to_string(), "a".to_string()]).expect("set pins");
+            hidden::set_hidden(&db, "qtassistant", true).expect("hide");
+        }
+
+        let mut cache = Cache::new(empty_loader);
+        assert_eq!(cache.pins("drun").expect("pins"), vec!["b", "a"]);
+        cache
+            .set_pins("drun", &["a".to_string()])
+            .expect("set pins");
+        cache.refresh().expect("refresh");
+        assert_eq!(cache.pins("drun").expect("pins"), vec!["a"]);
+        assert!(cache.pins("run").expect("pins").is_empty());
+
+        assert_eq!(cache.hidden_apps().expect("hidden"), vec!["qtassistant"]);
+        cache.set_hidden("avahi-discover", true).expect("hide");
+        cache.set_hidden("qtassistant", false).expect("show");
+        assert_eq!(cache.hidden_apps().expect("hidden"), vec!["avahi-discover"]);
+
+        drop(cache);
+        let _ = std::fs::remove_dir_all(previous);
+    }
+
+    #[test]
+    fn test_history_is_most_recent_first_without_duplicates() {
+        let _guard = prepare_test_cache();
+        let mut cache = Cache::new(empty_loader);
+
+        for entry in ["make", "ls -la", "make"] {
+            cache
+                .record_history("shell", entry)
+                .expect("record history");
+        }
+        cache
+            .record_history("other", "cargo test")
+            .expect("record history");
+
+        assert_eq!(
+            cache.history("shell").expect("history"),
+            vec!["make", "ls -la"]
+        );
+        assert_eq!(cache.history("other").expect("history"), vec!["cargo test"]);
+    }
 }
@@ -0,0 +1,266 @@
+//! Desktop entry discovery following the XDG desktop-file-ID precedence rules.
+//!
+//! See the [desktop entry spec](https://specifications.freedesktop.org/desktop-entry-spec/latest/file-naming.html)
+//! for how IDs are derived and which file wins when several directories provide the same ID.
+
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
+
+use freedesktop_desktop_entry::DesktopEntry;
+
+/// Compute the desktop-file ID of `path` relative to the `applications` directory `base`.
+///
+/// Subdirectory separators are replaced by `-`, so `base/kde/konsole.desktop` becomes `kde-konsole`.
+pub fn desktop_file_id(base: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(base).ok()?;
+    let mut parts = Vec::new();
+    for component in relative.components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_str()?),
+            _ => return None,
+        }
+    }
+
+    let file_name = parts.pop()?;
+    let stem = file_name.strip_suffix(".desktop")?;
+    if stem.is_empty() {
+        return None;
+    }
+    parts.push(stem);
+    Some(parts.join("-"))
+}
+
+/// Recursively collect `.desktop` files under `base` in a stable, sorted order.
+fn desktop_files(base: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut visited = HashSet::new();
+    let mut to_walk = vec![base.to_path_buf()];
+
+    while let Some(dir) = to_walk.pop() {
+        // Guard against symlink loops
+        let canonical = dir.canonicalize().unwrap_or_else(|_| dir.clone());
+        if !visited.insert(canonical) {
+            continue;
+        }
+
+        let Ok(read_dir) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        let mut children: Vec<PathBuf> = read_dir
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .collect();
+        children.sort_unstable();
+
+        let mut subdirs = Vec::new();
+        for child in children {
+            if child.is_dir() {
+                subdirs.push(child);
+            } else if child.extension().is_some_and(|ext| ext == "desktop") && child.is_file() {
+                files.push(child);
+            }
+        }
+        // Push in reverse so subdirectories are walked in sorted order
+        to_walk.extend(subdirs.into_iter().rev());
+    }
+
+    files
+}
+
+/// Walk `paths` in priority order, parsing the first file found for each desktop-file ID accepted
+/// by `wanted`.  Files for which `excluded` returns true are skipped as if they did not exist.
+fn collect_entries<I, L>(
+    paths: I,
+    locales: &[L],
+    excluded: impl Fn(&Path) -> bool,
+    wanted: impl Fn(&str) -> bool,
+) -> Vec<DesktopEntry>
+where
+    I: IntoIterator<Item = PathBuf>,
+    L: AsRef<str>,
+{
+    let mut seen_ids: HashSet<String> = HashSet::new();
+    let mut entries = Vec::new();
+
+    for base in paths {
+        for path in desktop_files(&base) {
+            if excluded(&path) {
+                continue;
+            }
+            let Some(id) = desktop_file_id(&base, &path) else {
+                continue;
+            };
+            if seen_ids.contains(&id) || !wanted(&id) {
+                continue;
+            }
+            let Ok(mut entry) = DesktopEntry::from_path(path, Some(locales)) else {
+                continue;
+            };
+            seen_ids.insert(id.clone());
+
+            if entry.hidden() {
+                continue;
+            }
+            entry.appid = id;
+            entries.push(entry);
+        }
+    }
+
+    entries
+}
+
+/// Load the effective set of desktop entries from `paths`, which must be ordered by priority
+/// (`XDG_DATA_HOME` first, then each of `XDG_DATA_DIRS`).
+///
+/// The first file found for a given desktop-file ID wins and shadows all later copies.  An entry
+/// with `Hidden=true` is treated as deleted: it is omitted and still shadows lower-priority copies.
+/// Each returned entry's `appid` is its desktop-file ID.  Files for which `excluded` returns true
+/// are skipped before precedence is applied, so they do not shadow lower-priority copies.
+pub fn desktop_entries<I, L>(
+    paths: I,
+    locales: &[L],
+    excluded: impl Fn(&Path) -> bool,
+) -> Vec<DesktopEntry>
+where
+    I: IntoIterator<Item = PathBuf>,
+    L: AsRef<str>,
+{
+    collect_entries(paths, locales, excluded, |_| true)
+}
+
+/// Resolve only the desktop-file IDs in `ids`, applying the same precedence rules as
+/// [`desktop_entries`].  IDs missing from the result no longer have a visible entry.
+pub fn desktop_entries_for_ids<I, L>(
+    paths: I,
+    ids: &HashSet<String>,
+    locales: &[L],
+    excluded: impl Fn(&Path) -> bool,
+) -> Vec<DesktopEntry>
+where
+    I: IntoIterator<Item = PathBuf>,
+    L: AsRef<str>,
+{
+    collect_entries(paths, locales, excluded, |id| ids.contains(id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let mut dir = std::env::temp_dir();
+        dir.push(format!(
+            "elbey-desktop-test-{}-{}",
+            std::process::id(),
+            name
+        ));
+        if dir.exists() {
+            let _ = std::fs::remove_dir_all(&dir);
+        }
+        std::fs::create_dir_all(&dir).expect("create test dir");
+        dir
+    }
+
+    fn write_entry(dir: &Path, relative: &str, name: &str, extra: &str) {
+        let path = dir.join(relative);
+        std::fs::create_dir_all(path.parent().unwrap()).expect("create entry dir");
+        let contents =
+            format!("[Desktop Entry]\nType=Application\nName={name}\nExec=/bin/true\n{extra}");
+        std::fs::write(path, contents).expect("write entry");
+    }
+
+    fn load(paths: &[PathBuf]) -> Vec<DesktopEntry> {
+        desktop_entries(paths.iter().cloned(), &[] as &[&str], |_| false)
+    }
+
+    #[test]
+    fn test_desktop_file_id_uses_dashes_for_subdirs() {
+        let base = Path::new("/usr/share/applications");
+        assert_eq!(
+            desktop_file_id(base, &base.join("kde/konsole.desktop")).as_deref(),
+            Some("kde-konsole")
+        );
+        assert_eq!(
+            desktop_file_id(base, &base.join("org.gnome.Maps.desktop")).as_deref(),
+            Some("org.gnome.Maps")
+        );
+        assert_eq!(desktop_file_id(base, Path::new("/tmp/foo.desktop")), None);
+        assert_eq!(desktop_file_id(base, &base.join("foo.txt")), None);
+    }
+
+    #[test]
+    fn test_excluded_copy_does_not_shadow_lower_priority_copy() {
+        let root = test_dir("excluded");
+        let user = root.join("user");
+        let system = root.join("system");
+        write_entry(&user, "foo.desktop", "User Foo", "");
+        write_entry(&system, "foo.desktop", "System Foo", "");
+
+        let entries = desktop_entries([user.clone(), system], &[] as &[&str], |path| {
+            path.starts_with(&user)
+        });
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].desktop_entry("Name"), Some("System Foo"));
+        assert_eq!(entries[0].appid, "foo");
+    }
+
+    #[test]
+    fn test_first_dir_wins_for_same_id() {
+        let root = test_dir("precedence");
+        let user = root.join("user");
+        let system = root.join("system");
+        write_entry(&user, "foo.desktop", "User Foo", "");
+        write_entry(&system, "foo.desktop", "System Foo", "");
+        write_entry(&system, "bar.desktop", "Bar", "");
+
+        let entries = load(&[user, system]);
+        let mut names: Vec<&str> = entries
+            .iter()
+            .map(|entry| entry.desktop_entry("Name").unwrap())
+            .collect();
+        names.sort_unstable();
+
+        assert_eq!(names, vec!["Bar", "User Foo"]);
+    }
+
+    #[test]
+    fn test_hidden_override_removes_entry() {
+        let root = test_dir("hidden");
+        let user = root.join("user");
+        let system = root.join("system");
+        write_entry(&user, "foo.desktop", "Foo", "Hidden=true\n");
+        write_entry(&system, "foo.desktop", "Foo", "");
+
+        assert!(load(&[user, system]).is_empty());
+    }
+
+    #[test]
+    fn test_subdir_prefix_shadows_flat_entry() {
+        let root = test_dir("subdir");
+        let user = root.join("user");
+        let system = root.join("system");
+        write_entry(&user, "kde-konsole.desktop", "User Konsole", "");
+        write_entry(&system, "kde/konsole.desktop", "System Konsole", "");
+
+        let entries = load(&[user, system]);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].appid, "kde-konsole");
+        assert_eq!(entries[0].desktop_entry("Name"), Some("User Konsole"));
+    }
+
+    #[test]
+    fn test_entries_for_ids_falls_back_to_lower_priority_copy() {
+        let root = test_dir("for-ids");
+        let user = root.join("user");
+        let system = root.join("system");
+        write_entry(&system, "foo.desktop", "System Foo", "");
+        write_entry(&system, "bar.desktop", "Bar", "");
+
+        let ids = HashSet::from(["foo".to_string()]);
+        let entries = desktop_entries_for_ids([user, system], &ids, &[] as &[&str], |_| false);
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].desktop_entry("Name"), Some("System Foo"));
+    }
+}
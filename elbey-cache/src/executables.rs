@@ -0,0 +1,94 @@
+//! Cached listing of the executables found in `$PATH` for run mode.
+//!
+//! Each directory's listing is stored with the directory's mtime, so a directory is only re-read
+//! when something was added to or removed from it.
+use std::collections::HashSet;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use serde::{Deserialize, Serialize};
+use sled::Db;
+
+const EXECUTABLES_TREE: &str = "executables";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedDir {
+    mtime: u128,
+    names: Vec<String>,
+}
+
+/// The directories listed in `$PATH`, in order and without duplicates.
+pub fn path_dirs() -> Vec<PathBuf> {
+    let mut seen = HashSet::new();
+    std::env::var_os("PATH")
+        .map(|path| {
+            std::env::split_paths(&path)
+                .filter(|dir| !dir.as_os_str().is_empty())
+                .filter(|dir| seen.insert(dir.clone()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn dir_mtime(dir: &Path) -> Option<u128> {
+    let modified = std::fs::metadata(dir).ok()?.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_nanos())
+}
+
+/// List the names of executable files in `dir`, following symlinks.
+fn scan_dir(dir: &Path) -> Vec<String> {
+    let Ok(read_dir) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut names: Vec<String> = read_dir
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            std::fs::metadata(entry.path())
+                .map(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+                .unwrap_or(false)
+        })
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect();
+    names.sort_unstable();
+    names
+}
+
+/// Return the executable names in `dirs`, rescanning only directories whose mtime changed.
+/// A name found in more than one directory is reported once, as `$PATH` lookup would resolve it.
+pub(crate) fn load_executables(db: &Db, dirs: &[PathBuf]) -> anyhow::Result<Vec<String>> {
+    let tree = db.open_tree(EXECUTABLES_TREE)?;
+    let mut seen = HashSet::new();
+    let mut executables = Vec::new();
+
+    for dir in dirs {
+        let key = dir.as_os_str().as_encoded_bytes();
+        let Some(mtime) = dir_mtime(dir) else {
+            tree.remove(key)?;
+            continue;
+        };
+
+        let cached = tree
+            .get(key)?
+            .and_then(|value| bincode::deserialize::<CachedDir>(&value).ok())
+            .filter(|cached| cached.mtime == mtime);
+        let names = match cached {
+            Some(cached) => cached.names,
+            None => {
+                let names = scan_dir(dir);
+                let encoded = bincode::serialize(&CachedDir {
+                    mtime,
+                    names: names.clone(),
+                })?;
+                tree.insert(key, encoded)?;
+                names
+            }
+        };
+
+        executables.extend(names.into_iter().filter(|name| seen.insert(name.clone())));
+    }
+
+    tree.flush()?;
+    Ok(executables)
+}
@@ -0,0 +1,26 @@
+//! Apps the user hid from the launcher, by app id.  Hidden apps stay in the app cache, keeping
+//! their usage counts for when they are shown again.
+use sled::Db;
+
+pub(crate) const HIDDEN_TREE: &str = "hidden";
+
+/// The ids of the hidden apps, in id order.
+pub(crate) fn hidden_apps(db: &Db) -> anyhow::Result<Vec<String>> {
+    let tree = db.open_tree(HIDDEN_TREE)?;
+    tree.iter()
+        .keys()
+        .map(|key| Ok(String::from_utf8_lossy(&key?).into_owned()))
+        .collect()
+}
+
+/// Hide or show the app `appid`.
+pub(crate) fn set_hidden(db: &Db, appid: &str, hidden: bool) -> anyhow::Result<()> {
+    let tree = db.open_tree(HIDDEN_TREE)?;
+    if hidden {
+        tree.insert(appid, &[])?;
+    } else {
+        tree.remove(appid)?;
+    }
+    tree.flush()?;
+    Ok(())
+}
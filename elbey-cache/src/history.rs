@@ -0,0 +1,30 @@
+//! Text entered in launcher modes, such as shell commands, most recent first.  Each mode keeps
+//! its own history.
+use sled::Db;
+
+const HISTORY_TREE: &str = "history";
+
+/// How many entries each mode keeps
+const MAX_HISTORY: usize = 100;
+
+/// The history of `mode`, most recent first.
+pub(crate) fn history(db: &Db, mode: &str) -> anyhow::Result<Vec<String>> {
+    let tree = db.open_tree(HISTORY_TREE)?;
+    Ok(tree
+        .get(mode)?
+        .and_then(|value| bincode::deserialize::<Vec<String>>(&value).ok())
+        .unwrap_or_default())
+}
+
+/// Add `entry` to the front of the history of `mode`, removing earlier copies of it.
+pub(crate) fn record(db: &Db, mode: &str, entry: &str) -> anyhow::Result<()> {
+    let mut entries = history(db, mode)?;
+    entries.retain(|existing| existing != entry);
+    entries.insert(0, entry.to_string());
+    entries.truncate(MAX_HISTORY);
+
+    let tree = db.open_tree(HISTORY_TREE)?;
+    tree.insert(mode, bincode::serialize(&entries)?)?;
+    tree.flush()?;
+    Ok(())
+}
@@ -6,7 +6,8 @@
 //! It uses iced types for images.  Data is returned in order of `exec_count`.  This value
 //! is updated each time an app is launched by the user.
 //!
-//! Clients should call refresh() off the critical path to refresh the cache from Desktop apps on disk
+//! Clients should call refresh() off the critical path to refresh the cache from Desktop apps on disk,
+//! or use [`watch_desktop_dirs`] and [`Cache::apply_changes`] to keep it current as entries change.
 
 use std::collections::HashMap;
 use std::path::PathBuf;
@@ -18,8 +19,20 @@ use iced::widget::svg::Handle as SvgHandle;
 use serde::{Deserialize, Serialize};
 
 mod cache;
+mod desktop;
+mod executables;
+mod hidden;
+mod history;
+mod man_pages;
+mod pins;
+mod usage;
+mod watch;
 
-pub use cache::{clear_cache_dir, Cache};
+pub use cache::{clear_cache_dir, load_icon, Cache};
+pub use desktop::{desktop_entries, desktop_entries_for_ids, desktop_file_id};
+pub use executables::path_dirs;
+pub use man_pages::{man_dirs, ManPage};
+pub use watch::{watch_desktop_dirs, WatchEvent, DEFAULT_WATCH_DEBOUNCE};
 
 /// Default icon size for freedesktop icon lookups.
 pub const DEFAULT_ICON_SIZE: u16 = 32;
@@ -0,0 +1,329 @@
+//! Cached index of the man pages on the manual search path for man mode.
+//!
+//! Each section directory's pages are stored with the directory's mtime, so a section is only
+//! re-read when a page was added to or removed from it.  Descriptions come from a `whatis` file
+//! in the man directory where one exists, and otherwise from the NAME section of each page.
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::UNIX_EPOCH;
+
+use flate2::read::GzDecoder;
+use serde::{Deserialize, Serialize};
+use sled::Db;
+
+const MAN_PAGES_TREE: &str = "man_pages";
+
+/// Man directories searched when neither `manpath` nor `$MANPATH` says otherwise
+const DEFAULT_MAN_DIRS: &[&str] = &["/usr/local/share/man", "/usr/share/man", "/usr/local/man"];
+
+/// How much of a page is read to find its NAME section
+const MAX_HEAD_BYTES: u64 = 8192;
+
+/// A man page: `man <section> <name>` opens it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManPage {
+    pub name: String,
+    /// The section as in the file name, e.g. `1` or `3ssl`
+    pub section: String,
+    /// The one-line description from the NAME section
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedSection {
+    mtime: u128,
+    pages: Vec<ManPage>,
+}
+
+/// The man directories to index: the output of `manpath`, else `$MANPATH`, else the usual
+/// system directories.  Empty `$MANPATH` components stand for the system directories.
+pub fn man_dirs() -> Vec<PathBuf> {
+    let manpath = Command::new("manpath")
+        .arg("-q")
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|output| output.trim().to_string())
+        .filter(|output| !output.is_empty())
+        .or_else(|| std::env::var("MANPATH").ok())
+        .unwrap_or_default();
+
+    let mut seen = HashSet::new();
+    let mut dirs = Vec::new();
+    let defaults = || DEFAULT_MAN_DIRS.iter().map(PathBuf::from);
+    if manpath.is_empty() {
+        dirs.extend(defaults());
+    }
+    for component in manpath.split(':') {
+        if component.is_empty() {
+            dirs.extend(defaults());
+        } else {
+            dirs.push(PathBuf::from(component));
+        }
+    }
+    dirs.retain(|dir| seen.insert(dir.clone()));
+    dirs
+}
+
+fn mtime(path: &Path) -> Option<u128> {
+    let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_nanos())
+}
+
+/// The section directories of a man directory, e.g. `man1` and `man3p`.
+fn section_dirs(man_dir: &Path) -> Vec<PathBuf> {
+    let Ok(read_dir) = std::fs::read_dir(man_dir) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<PathBuf> = read_dir
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            name.strip_prefix("man")
+                .is_some_and(|section| section.starts_with(|c: char| c.is_ascii_alphanumeric()))
+                && entry.path().is_dir()
+        })
+        .map(|entry| entry.path())
+        .collect();
+    dirs.sort_unstable();
+    dirs
+}
+
+/// Split a page file name such as `ls.1.gz` into its name and section.
+fn page_name(file_name: &str) -> Option<(&str, &str)> {
+    let stem = [".gz", ".bz2", ".xz", ".lzma", ".zst", ".Z"]
+        .iter()
+        .find_map(|ext| file_name.strip_suffix(ext))
+        .unwrap_or(file_name);
+    let (name, section) = stem.rsplit_once('.')?;
+    (!name.is_empty() && section.starts_with(|c: char| c.is_ascii_digit() || c == 'n'))
+        .then_some((name, section))
+}
+
+/// Remove the common roff escapes and font changes from a line of text.
+fn strip_roff(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            plain.push(c);
+            continue;
+        }
+        match chars.next() {
+            // \fB, \fI, \fR, \fP and \f(CW
+            Some('f') if chars.next() == Some('(') => {
+                chars.next();
+                chars.next();
+            }
+            Some('f') => {}
+            Some('(') => {
+                let name: String = chars.by_ref().take(2).collect();
+                plain.push_str(match name.as_str() {
+                    "em" | "en" => "-",
+                    "aq" => "'",
+                    "dq" => "\"",
+                    _ => "",
+                });
+            }
+            Some('-') => plain.push('-'),
+            Some('&') | Some('c') | Some('%') => {}
+            Some('e') | Some('\\') => plain.push('\\'),
+            Some(other) => plain.push(other),
+            None => {}
+        }
+    }
+    plain
+}
+
+/// The description in the NAME section of a page's roff source: the text after the `-` of
+/// `name \- description`, or the `.Nd` line of an mdoc page.
+fn description_in(source: &str) -> Option<String> {
+    let mut lines = source.lines().skip_while(|line| {
+        let line = line.trim().trim_matches('"');
+        let header = line
+            .strip_prefix(".SH")
+            .or_else(|| line.strip_prefix(".Sh"))
+            .map(|title| title.trim().trim_matches('"'));
+        !header.is_some_and(|title| title.eq_ignore_ascii_case("NAME"))
+    });
+    lines.next()?;
+
+    let mut text = String::new();
+    for line in lines {
+        if line.starts_with(".SH") || line.starts_with(".Sh") {
+            break;
+        }
+        if let Some(description) = line.strip_prefix(".Nd ") {
+            return Some(strip_roff(description).trim().to_string());
+        }
+        // Keep the text of font macros such as `.B name`, skipping other requests and comments
+        let content = match line.split_once(' ') {
+            Some((".B" | ".I" | ".BR" | ".IR" | ".Nm", rest)) => rest,
+            _ if line.starts_with('.') || line.starts_with('\'') => continue,
+            _ => line,
+        };
+        text.push(' ');
+        text.push_str(&strip_roff(content));
+    }
+    let (_, description) = text.split_once(" - ")?;
+    let description = description.trim();
+    (!description.is_empty()).then(|| description.to_string())
+}
+
+/// Read the start of a page, decompressing it if it is gzipped.
+fn read_head(path: &Path) -> Option<String> {
+    let file = std::fs::File::open(path).ok()?;
+    let mut head = Vec::new();
+    let name = path.file_name()?.to_string_lossy();
+    if name.ends_with(".gz") {
+        GzDecoder::new(file)
+            .take(MAX_HEAD_BYTES)
+            .read_to_end(&mut head)
+            .ok()?;
+    } else if page_name(&name).is_some_and(|(_, section)| !name.ends_with(section)) {
+        // Other compression formats are not read
+        return None;
+    } else {
+        file.take(MAX_HEAD_BYTES).read_to_end(&mut head).ok()?;
+    }
+    Some(String::from_utf8_lossy(&head).into_owned())
+}
+
+/// Parse a `whatis` file of `name (section) - description` lines, keyed by name and section.
+fn parse_whatis(contents: &str) -> HashMap<(String, String), String> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let (names, description) = line.split_once(" - ")?;
+            let (name, section) = names.trim().rsplit_once('(')?;
+            let section = section.strip_suffix(')')?.trim();
+            let name = name.trim().split(',').next()?.trim();
+            Some((
+                (name.to_string(), section.to_string()),
+                description.trim().to_string(),
+            ))
+        })
+        .collect()
+}
+
+/// List the pages in a section directory.
+fn scan_section(dir: &Path, whatis: &HashMap<(String, String), String>) -> Vec<ManPage> {
+    let Ok(read_dir) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut pages: Vec<ManPage> = read_dir
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let file_name = entry.file_name().into_string().ok()?;
+            let (name, section) = page_name(&file_name)?;
+            let key = (name.to_string(), section.to_string());
+            let description = match whatis.get(&key) {
+                Some(description) => Some(description.clone()),
+                None => read_head(&entry.path()).and_then(|source| description_in(&source)),
+            };
+            Some(ManPage {
+                name: key.0,
+                section: key.1,
+                description,
+            })
+        })
+        .collect();
+    pages.sort_unstable_by(|a, b| (&a.name, &a.section).cmp(&(&b.name, &b.section)));
+    pages
+}
+
+/// Return the pages in `man_dirs`, rescanning only section directories whose mtime, or the mtime
+/// of their man directory's `whatis` file, changed.  A page found in more than one directory is
+/// reported once, as `man` would resolve it.
+pub(crate) fn load_man_pages(db: &Db, man_dirs: &[PathBuf]) -> anyhow::Result<Vec<ManPage>> {
+    let tree = db.open_tree(MAN_PAGES_TREE)?;
+    let mut seen = HashSet::new();
+    let mut pages = Vec::new();
+
+    for man_dir in man_dirs {
+        let whatis_path = man_dir.join("whatis");
+        let whatis_mtime = mtime(&whatis_path).unwrap_or(0);
+        let mut whatis = None;
+
+        for dir in section_dirs(man_dir) {
+            let key = dir.as_os_str().as_encoded_bytes();
+            let Some(dir_mtime) = mtime(&dir) else {
+                continue;
+            };
+            let section_mtime = dir_mtime.max(whatis_mtime);
+
+            let cached = tree
+                .get(key)?
+                .and_then(|value| bincode::deserialize::<CachedSection>(&value).ok())
+                .filter(|cached| cached.mtime == section_mtime);
+            let section_pages = match cached {
+                Some(cached) => cached.pages,
+                None => {
+                    let whatis = whatis.get_or_insert_with(|| {
+                        std::fs::read_to_string(&whatis_path)
+                            .map(|contents| parse_whatis(&contents))
+                            .unwrap_or_default()
+                    });
+                    let section_pages = scan_section(&dir, whatis);
+                    let encoded = bincode::serialize(&CachedSection {
+                        mtime: section_mtime,
+                        pages: section_pages.clone(),
+                    })?;
+                    tree.insert(key, encoded)?;
+                    section_pages
+                }
+            };
+
+            pages.extend(
+                section_pages
+                    .into_iter()
+                    .filter(|page| seen.insert((page.name.clone(), page.section.clone()))),
+            );
+        }
+    }
+
+    tree.flush()?;
+    Ok(pages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_names_and_descriptions() {
+        assert_eq!(page_name("ls.1.gz"), Some(("ls", "1")));
+        assert_eq!(page_name("SSL_read.3ssl"), Some(("SSL_read", "3ssl")));
+        assert_eq!(page_name("git-log.1.xz"), Some(("git-log", "1")));
+        assert_eq!(page_name("README"), None);
+
+        let man = ".TH LS 1\n.SH NAME\nls \\- list directory contents\n.SH SYNOPSIS\n.B ls\n";
+        assert_eq!(
+            description_in(man).as_deref(),
+            Some("list directory contents")
+        );
+        let quoted = ".SH \"NAME\"\n.B grep, egrep\n\\- print lines that match \\fIpatterns\\fR\n";
+        assert_eq!(
+            description_in(quoted).as_deref(),
+            Some("print lines that match patterns")
+        );
+        let mdoc = ".Sh NAME\n.Nm ssh\n.Nd OpenSSH remote login client\n.Sh SYNOPSIS\n";
+        assert_eq!(
+            description_in(mdoc).as_deref(),
+            Some("OpenSSH remote login client")
+        );
+        assert_eq!(description_in(".so man3/other.3\n"), None);
+
+        let whatis = parse_whatis("ls (1)               - list directory contents\nbad line\n");
+        assert_eq!(
+            whatis
+                .get(&("ls".to_string(), "1".to_string()))
+                .map(String::as_str),
+            Some("list directory contents")
+        );
+    }
+}
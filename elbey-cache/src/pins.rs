@@ -0,0 +1,22 @@
+//! Items pinned to the top of a launcher mode's list, in the order the user chose.  Each mode
+//! keeps its own pins.
+use sled::Db;
+
+pub(crate) const PINS_TREE: &str = "pins";
+
+/// The keys of the items pinned in `mode`, in order.
+pub(crate) fn pins(db: &Db, mode: &str) -> anyhow::Result<Vec<String>> {
+    let tree = db.open_tree(PINS_TREE)?;
+    Ok(tree
+        .get(mode)?
+        .and_then(|value| bincode::deserialize::<Vec<String>>(&value).ok())
+        .unwrap_or_default())
+}
+
+/// Replace the pins of `mode`.
+pub(crate) fn set_pins(db: &Db, mode: &str, pins: &[String]) -> anyhow::Result<()> {
+    let tree = db.open_tree(PINS_TREE)?;
+    tree.insert(mode, bincode::serialize(pins)?)?;
+    tree.flush()?;
+    Ok(())
+}
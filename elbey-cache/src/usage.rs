@@ -0,0 +1,33 @@
+//! Usage counts of items picked in launcher modes other than desktop apps, such as run mode
+//! commands or emoji.  Each mode keeps its counts in its own tree, keyed by item.
+use std::collections::HashMap;
+
+use sled::Db;
+
+fn tree_name(mode: &str) -> String {
+    format!("{mode}_usage")
+}
+
+/// Usage counts of the items picked in `mode`.
+pub(crate) fn counts(db: &Db, mode: &str) -> anyhow::Result<HashMap<String, usize>> {
+    let tree = db.open_tree(tree_name(mode))?;
+    let mut counts = HashMap::new();
+    for item in tree.iter() {
+        let (key, value) = item?;
+        let key = String::from_utf8_lossy(&key).into_owned();
+        counts.insert(key, bincode::deserialize::<usize>(&value)?);
+    }
+    Ok(counts)
+}
+
+/// Increment the usage count of `key` in `mode`.
+pub(crate) fn record(db: &Db, mode: &str, key: &str) -> anyhow::Result<()> {
+    let tree = db.open_tree(tree_name(mode))?;
+    let count = tree
+        .get(key)?
+        .and_then(|value| bincode::deserialize::<usize>(&value).ok())
+        .unwrap_or(0);
+    tree.insert(key, bincode::serialize(&(count + 1))?)?;
+    tree.flush()?;
+    Ok(())
+}
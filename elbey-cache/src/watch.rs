@@ -0,0 +1,293 @@
+//! Debounced inotify watcher over desktop entry directories.
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::time::Duration;
+
+use inotify::{EventMask, Inotify, WatchDescriptor, WatchMask};
+
+/// Default quiet period before a batch of filesystem changes is reported.
+pub const DEFAULT_WATCH_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// A batch of changes observed in the watched directories.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WatchEvent {
+    /// The listed desktop files were created, modified, moved or deleted.
+    Changed(Vec<PathBuf>),
+    /// A directory changed or events were dropped; a full rescan is required.
+    Rescan,
+}
+
+fn watch_mask() -> WatchMask {
+    WatchMask::CREATE
+        | WatchMask::CLOSE_WRITE
+        | WatchMask::DELETE
+        | WatchMask::MOVED_FROM
+        | WatchMask::MOVED_TO
+        | WatchMask::DELETE_SELF
+}
+
+fn is_desktop_file(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "desktop")
+}
+
+/// The inotify watches over the search directories, and over the nearest existing ancestor of
+/// each search directory that does not exist yet, so it is watched once it is created.
+struct Watches {
+    inotify: Inotify,
+    /// The watched search directories and their subdirectories
+    watched: HashMap<WatchDescriptor, PathBuf>,
+    /// Ancestors watched for the creation of a missing search directory
+    ancestors: HashMap<WatchDescriptor, PathBuf>,
+    /// Search directories that do not exist yet
+    missing: Vec<PathBuf>,
+}
+
+impl Watches {
+    fn new(inotify: Inotify, dirs: impl IntoIterator<Item = PathBuf>) -> Self {
+        let mut watches = Watches {
+            inotify,
+            watched: HashMap::new(),
+            ancestors: HashMap::new(),
+            missing: dirs.into_iter().collect(),
+        };
+        watches.add_missing();
+        watches
+    }
+
+    /// Add watches on `dir` and all of its subdirectories.
+    fn add_recursive(&mut self, dir: &Path) {
+        let mut to_walk = vec![dir.to_path_buf()];
+        while let Some(dir) = to_walk.pop() {
+            let Ok(wd) = self.inotify.watches().add(&dir, watch_mask()) else {
+                continue;
+            };
+            self.ancestors.remove(&wd);
+            if self.watched.insert(wd, dir.clone()).is_some() {
+                // Already watched, e.g. via a symlink loop
+                continue;
+            }
+            if let Ok(read_dir) = std::fs::read_dir(&dir) {
+                to_walk.extend(
+                    read_dir
+                        .filter_map(Result::ok)
+                        .map(|entry| entry.path())
+                        .filter(|path| path.is_dir()),
+                );
+            }
+        }
+    }
+
+    /// Watch the missing search directories that now exist, and the nearest existing ancestor of
+    /// the others.  Returns true if a search directory appeared.
+    fn add_missing(&mut self) -> bool {
+        let (appeared, missing): (Vec<_>, Vec<_>) = std::mem::take(&mut self.missing)
+            .into_iter()
+            .partition(|dir| dir.is_dir());
+        for dir in &appeared {
+            self.add_recursive(dir);
+        }
+        for dir in &missing {
+            let Some(ancestor) = dir.ancestors().skip(1).find(|ancestor| ancestor.is_dir()) else {
+                continue;
+            };
+            if let Ok(wd) = self.inotify.watches().add(ancestor, watch_mask()) {
+                if !self.watched.contains_key(&wd) {
+                    self.ancestors.insert(wd, ancestor.to_path_buf());
+                }
+            }
+        }
+        self.missing = missing;
+        !appeared.is_empty()
+    }
+}
+
+/// Block on inotify and forward raw events until the receiving side hangs up.
+fn run_inotify(mut watches: Watches, sender: Sender<WatchEvent>) {
+    let mut buffer = [0; 4096];
+    loop {
+        let events = match watches.inotify.read_events_blocking(&mut buffer) {
+            Ok(events) => events,
+            Err(err) => {
+                eprintln!("Failed to read inotify events: {err}");
+                return;
+            }
+        };
+
+        let mut changed = Vec::new();
+        let mut rescan = false;
+        let mut new_dirs = Vec::new();
+        let mut ancestor_changed = false;
+        for event in events {
+            if event.mask.contains(EventMask::Q_OVERFLOW) {
+                rescan = true;
+                continue;
+            }
+            let is_new_dir = event.mask.contains(EventMask::ISDIR)
+                && event
+                    .mask
+                    .intersects(EventMask::CREATE | EventMask::MOVED_TO);
+            if watches.ancestors.contains_key(&event.wd) {
+                ancestor_changed |= is_new_dir;
+                continue;
+            }
+            let Some(dir) = watches.watched.get(&event.wd) else {
+                continue;
+            };
+            if event.mask.contains(EventMask::ISDIR) || event.mask.contains(EventMask::DELETE_SELF)
+            {
+                if is_new_dir {
+                    if let Some(name) = event.name {
+                        new_dirs.push(dir.join(name));
+                    }
+                    ancestor_changed = true;
+                }
+                rescan = true;
+                continue;
+            }
+            if let Some(name) = event.name {
+                let path = dir.join(name);
+                if is_desktop_file(&path) {
+                    changed.push(path);
+                }
+            }
+        }
+
+        for dir in new_dirs {
+            watches.add_recursive(&dir);
+        }
+        // A created directory may be, or lead to, a search directory that was missing
+        if ancestor_changed && !watches.missing.is_empty() && watches.add_missing() {
+            rescan = true;
+        }
+
+        let result = if rescan {
+            sender.send(WatchEvent::Rescan)
+        } else if !changed.is_empty() {
+            sender.send(WatchEvent::Changed(changed))
+        } else {
+            Ok(())
+        };
+        if result.is_err() {
+            return;
+        }
+    }
+}
+
+/// Coalesce raw events until no new event arrives for `debounce`.
+fn run_debounce(raw: Receiver<WatchEvent>, sender: Sender<WatchEvent>, debounce: Duration) {
+    while let Ok(first) = raw.recv() {
+        let mut rescan = false;
+        let mut changed = BTreeSet::new();
+        let mut merge = |event: WatchEvent| match event {
+            WatchEvent::Rescan => rescan = true,
+            WatchEvent::Changed(paths) => changed.extend(paths),
+        };
+        merge(first);
+
+        loop {
+            match raw.recv_timeout(debounce) {
+                Ok(event) => merge(event),
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+        }
+
+        let batch = if rescan {
+            WatchEvent::Rescan
+        } else {
+            WatchEvent::Changed(changed.into_iter().collect())
+        };
+        if sender.send(batch).is_err() {
+            return;
+        }
+    }
+}
+
+/// Watch `dirs` recursively for desktop file changes on background threads.
+///
+/// Events are debounced: a batch is delivered once the directories have been quiet for
+/// `debounce`.  Directories that do not exist yet are watched from their nearest existing
+/// ancestor, and a rescan is requested when they are created.
+pub fn watch_desktop_dirs(
+    dirs: impl IntoIterator<Item = PathBuf>,
+    debounce: Duration,
+) -> anyhow::Result<Receiver<WatchEvent>> {
+    let watches = Watches::new(Inotify::init()?, dirs);
+
+    let (raw_sender, raw_receiver) = channel();
+    let (sender, receiver) = channel();
+    std::thread::spawn(move || run_inotify(watches, raw_sender));
+    std::thread::spawn(move || run_debounce(raw_receiver, sender, debounce));
+
+    Ok(receiver)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let mut dir = std::env::temp_dir();
+        dir.push(format!("elbey-watch-test-{}-{}", std::process::id(), name));
+        if dir.exists() {
+            let _ = std::fs::remove_dir_all(&dir);
+        }
+        std::fs::create_dir_all(&dir).expect("create test dir");
+        dir
+    }
+
+    #[test]
+    fn test_debounced_desktop_file_changes() {
+        let dir = test_dir("changes");
+        let receiver =
+            watch_desktop_dirs([dir.clone()], Duration::from_millis(100)).expect("start watcher");
+
+        std::fs::write(dir.join("a.desktop"), "[Desktop Entry]\n").expect("write a");
+        std::fs::write(dir.join("b.desktop"), "[Desktop Entry]\n").expect("write b");
+        std::fs::write(dir.join("ignored.txt"), "").expect("write ignored");
+        std::fs::write(dir.join("a.desktop"), "[Desktop Entry]\nName=A\n").expect("rewrite a");
+
+        let event = receiver
+            .recv_timeout(Duration::from_secs(5))
+            .expect("receive batch");
+        assert_eq!(
+            event,
+            WatchEvent::Changed(vec![dir.join("a.desktop"), dir.join("b.desktop")])
+        );
+    }
+
+    #[test]
+    fn test_new_subdirectory_requests_rescan() {
+        let dir = test_dir("subdir");
+        let receiver =
+            watch_desktop_dirs([dir.clone()], Duration::from_millis(100)).expect("start watcher");
+
+        std::fs::create_dir(dir.join("kde")).expect("create subdir");
+
+        let event = receiver
+            .recv_timeout(Duration::from_secs(5))
+            .expect("receive batch");
+        assert_eq!(event, WatchEvent::Rescan);
+    }
+
+    #[test]
+    fn test_missing_directory_is_watched_once_created() {
+        let base = test_dir("missing");
+        let dir = base.join("share").join("applications");
+        let receiver =
+            watch_desktop_dirs([dir.clone()], Duration::from_millis(100)).expect("start watcher");
+
+        std::fs::create_dir_all(&dir).expect("create search dir");
+        let event = receiver
+            .recv_timeout(Duration::from_secs(5))
+            .expect("receive batch");
+        assert_eq!(event, WatchEvent::Rescan);
+
+        std::fs::write(dir.join("a.desktop"), "[Desktop Entry]\n").expect("write a");
+        let event = receiver
+            .recv_timeout(Duration::from_secs(5))
+            .expect("receive batch");
+        assert_eq!(event, WatchEvent::Changed(vec![dir.join("a.desktop")]));
+    }
+}
@@ -1,30 +1,22 @@
-//! Functions and other types for `iced` UI to view, filter, and launch apps
+//! Functions and other types for `iced` UI to view, filter, and act on provider items
 use std::cmp::{max, min};
 use std::process::exit;
+use std::sync::Arc;
 
-use elbey_cache::AppDescriptor;
 use iced::keyboard::key::Named;
 use iced::keyboard::Key;
-use iced::widget::button::{primary, text as text_style};
-use iced::widget::operation::focus;
-use iced::widget::{
-    button, column, container, image, row, scrollable, svg, text, text_input, Column,
+use iced::widget::button::{primary, success, text as text_style};
+use iced::widget::operation::{focus, move_cursor_to_end};
+use iced::widget::{button, container, image, row, scrollable, svg, text, text_input, Column, Row};
+use iced::{
+    border, event, window, Alignment, Element, Event, Length, Pixels, Subscription, Task, Theme,
 };
-use iced::{border, event, window, Alignment, Element, Event, Length, Pixels, Task, Theme};
 use iced_layershell::to_layer_message;
 
+use crate::provider::{Activation, Item, ItemsUpdate, Listing, Provider};
 use crate::values::*;
-use crate::CACHE;
 use crate::PROGRAM_NAME;
 
-fn persist_cache_snapshot(apps: &[AppDescriptor]) {
-    if let Ok(mut cache) = CACHE.lock() {
-        if let Err(e) = cache.save_snapshot(apps) {
-            eprintln!("Failed to persist cache snapshot: {e}");
-        }
-    }
-}
-
 fn default_icon_handle() -> IconHandle {
     FALLBACK_ICON_HANDLE.clone()
 }
@@ -36,12 +28,30 @@ pub struct State {
     entry: String,
     /// Lowercased entry text to avoid repeated allocations during filtering
     entry_lower: String,
-    /// The complete list of DesktopEntry, as retrieved by lib
-    apps: Vec<AppDescriptor>,
-    /// Indices of apps that match the current filter, to avoid re-filtering
+    /// The items of each provider, indexed like `Elbey::providers`; `None` until loaded
+    provider_items: Vec<Option<Vec<Item>>>,
+    /// The items of the active tab, as (provider index, item index) pairs
+    items: Vec<(usize, usize)>,
+    /// Indices of items that match the current filter, to avoid re-filtering
     filtered_indices: Vec<usize>,
+    /// Items computed from the query by the providers of the active tab, listed first
+    query_items: Vec<(usize, Item)>,
+    /// Items offered by the fallback providers when nothing else matches the query, listed last
+    fallback_items: Vec<(usize, Item)>,
+    /// The position in the query history of the recalled query shown in the entry box
+    history_position: Option<usize>,
     /// The index of the item visibly selected in the UI
     selected_index: usize,
+    /// Items marked for multi-select, as (provider index, item index) pairs in the order marked
+    marked: Vec<(usize, usize)>,
+    /// The index of the active tab
+    tab: usize,
+    /// A notice or error from a provider, shown above the list
+    message: Option<String>,
+    /// Text from a provider replacing the hint in the entry box
+    prompt: Option<String>,
+    /// Whether a provider is still acting on a choice; other choices are ignored until it is done
+    activating: bool,
     /// A flag to indicate app window has received focus. Work around to some windowing environments passing `unfocused` unexpectedly.
     received_focus: bool,
 }
@@ -51,18 +61,33 @@ pub struct State {
 pub struct Elbey {
     state: State,
     flags: ElbeyFlags,
+    /// Every provider, across all tabs
+    providers: Vec<Arc<dyn Provider>>,
+    /// The providers listed together in each tab, as indices into `providers`
+    tabs: Vec<Vec<usize>>,
+    /// The providers offering items when nothing matches, as indices into `providers`
+    fallbacks: Vec<usize>,
+    /// The providers taking over the list in every tab, as indices into `providers`
+    exclusive: Vec<usize>,
 }
 
 /// Messages are how your logic mutates the app state and GUI
 #[to_layer_message]
 #[derive(Debug, Clone)]
 pub enum ElbeyMessage {
-    /// Signals that the `DesktopEntries` have been fully loaded into the vec
-    ModelLoaded(Vec<AppDescriptor>),
+    /// Signals that the items of a provider have been fully loaded
+    ItemsLoaded(usize, Listing),
+    /// Signals that the items of a provider changed while the launcher is open
+    ItemsUpdated(usize, ItemsUpdate),
     /// Signals that the primary text edit box on the UI has been changed by the user, including the new text.
     EntryUpdate(String),
     /// Signals that the user has taken primary action on a selection.  In the case of a desktop app launcher, the app is launched.
     ExecuteSelected(),
+    /// Signals that the user has taken the secondary action on a selection with Ctrl+Enter
+    ExecuteAlternate,
+    /// Signals that the providers acting on a choice finished, with the outcome of each by
+    /// provider index
+    Activated(Vec<(usize, Result<Activation, String>)>),
     /// Signals that the user has pressed a key
     KeyEvent(Key),
     /// Signals that the window has gained focus
@@ -71,19 +96,33 @@ pub enum ElbeyMessage {
     LostFocus,
     /// Triggers a follow-up render after initial model load.
     PostLoadRefresh,
+    /// Signals that the user asked to show the next tab
+    SwitchTab,
+    /// Signals that the user clicked a tab to show it
+    SelectTab(usize),
+    /// Signals that the user marked or unmarked the selected item for multi-select
+    ToggleMark,
+    /// Signals that the user pinned or unpinned the selected item with Ctrl+P
+    TogglePin,
+    /// Signals that the user moved the selected pinned item up or down with Ctrl+Up or Ctrl+Down
+    MovePin(i32),
+    /// Signals that the user hid the selected item with Ctrl+H
+    HideSelected,
 }
 
 /// Provide some initial configuration to app to facilitate testing
 #[derive(Debug, Clone)]
 pub struct ElbeyFlags {
-    /**
-     * A function that returns a list of `DesktopEntry`s
-     */
-    pub apps_loader: fn() -> Vec<AppDescriptor>,
-    /**
-     * A function that launches a process from a `DesktopEntry`
-     */
-    pub app_launcher: fn(&AppDescriptor) -> anyhow::Result<()>, //TODO ~ return a task that exits app
+    /// The providers of each tab.  Providers sharing a tab are listed together, and Ctrl+Tab
+    /// cycles through the tabs.
+    pub tabs: Vec<Vec<Arc<dyn Provider>>>,
+
+    /// Providers whose query items are listed, in order, when the query matches nothing else.
+    pub fallbacks: Vec<Arc<dyn Provider>>,
+
+    /// Providers that, in every tab, list only their own query items when the query is
+    /// addressed to them, such as a typed path.
+    pub exclusive: Vec<Arc<dyn Provider>>,
 
     pub theme: Theme,
 
@@ -97,32 +136,62 @@ pub struct ElbeyFlags {
 
     /// Font size for the entry list items.
     pub entries_font_size: u16,
+
+    /// Match the query against items comparing case.
+    pub case_sensitive: bool,
+
+    /// Allow marking several items with Shift+Enter to act on them together.
+    pub multi_select: bool,
 }
 
 impl Elbey {
     /// Initialize the app.  Only notable item here is probably the return type Task<ElbeyMessage> and what we pass
-    /// back.  Here, within the async execution, we directly call the library to retrieve `DesktopEntry`'s which
-    /// are the primary model of the [XDG Desktop Specification](https://www.freedesktop.org/wiki/Specifications/desktop-entry-spec/).
+    /// back.  Here, within the async execution, we ask the providers of the first tab for their items, such as the
+    /// `DesktopEntry`'s which are the primary model of the [XDG Desktop Specification](https://www.freedesktop.org/wiki/Specifications/desktop-entry-spec/).
     /// Then we create and pass a layer shell as another task.
     pub fn new(flags: ElbeyFlags) -> (Self, Task<ElbeyMessage>) {
-        // A task to load the app model
-        let apps_loader = flags.apps_loader;
-        let load_task = Task::perform(async move { (apps_loader)() }, ElbeyMessage::ModelLoaded);
-
-        (
-            Self {
-                state: State {
-                    entry: String::new(),
-                    entry_lower: String::new(),
-                    apps: vec![],
-                    filtered_indices: vec![],
-                    selected_index: 0,
-                    received_focus: false,
-                },
-                flags,
+        let mut providers = Vec::new();
+        let mut tabs = Vec::new();
+        for tab in &flags.tabs {
+            let first = providers.len();
+            providers.extend(tab.iter().cloned());
+            tabs.push((first..providers.len()).collect());
+        }
+        let first_fallback = providers.len();
+        providers.extend(flags.fallbacks.iter().cloned());
+        let fallbacks = (first_fallback..providers.len()).collect();
+        let first_exclusive = providers.len();
+        providers.extend(flags.exclusive.iter().cloned());
+        let exclusive = (first_exclusive..providers.len()).collect();
+
+        let elbey = Self {
+            state: State {
+                entry: String::new(),
+                entry_lower: String::new(),
+                provider_items: vec![None; providers.len()],
+                items: vec![],
+                filtered_indices: vec![],
+                query_items: vec![],
+                fallback_items: vec![],
+                history_position: None,
+                selected_index: 0,
+                marked: vec![],
+                tab: 0,
+                message: None,
+                prompt: None,
+                activating: false,
+                received_focus: false,
             },
-            load_task,
-        )
+            flags,
+            providers,
+            tabs,
+            fallbacks,
+            exclusive,
+        };
+        // A task to load the item model
+        let load_task = elbey.load_tab();
+
+        (elbey, load_task)
     }
 
     pub fn namespace() -> String {
@@ -131,26 +200,19 @@ impl Elbey {
 
     /// Entry-point from `iced`` into app to construct UI
     pub fn view(&self) -> Element<'_, ElbeyMessage> {
-        // Create the list UI elements based on the `DesktopEntry` model
-        let app_elements: Vec<Element<ElbeyMessage>> = self
-            .state
-            .filtered_indices
-            .iter()
-            .enumerate()
-            .filter_map(|(filtered_index, original_index)| {
-                self.state
-                    .apps
-                    .get(*original_index)
-                    .map(|entry| (filtered_index, entry))
-            })
-            .filter(|(filtered_index, _)| {
+        // Create the list UI elements based on the items of the active tab
+        let item_elements: Vec<Element<ElbeyMessage>> = (0..self.row_count())
+            .filter(|row| {
                 (self.state.selected_index..self.state.selected_index + VIEWABLE_LIST_ITEM_COUNT)
-                    .contains(filtered_index)
+                    .contains(row)
             }) // Only show entries in selection range
-            .map(|(filtered_index, entry)| {
-                let name = entry.title.as_str();
-                let selected = self.state.selected_index == filtered_index;
-                let icon_handle_to_render = match &entry.icon_handle {
+            .filter_map(|row| self.row(row).map(|(_, entry)| (row, entry)))
+            .map(|(row, entry)| {
+                let selected = self.state.selected_index == row;
+                let marked = self
+                    .row_position(row)
+                    .is_some_and(|position| self.state.marked.contains(&position));
+                let icon_handle_to_render = match &entry.icon {
                     IconHandle::NotLoaded => default_icon_handle(),
                     other => other.clone(),
                 };
@@ -165,15 +227,27 @@ impl Elbey {
                         .into(),
                     IconHandle::NotLoaded => unreachable!(),
                 };
-                let content = row![
-                    icon,
-                    text(name).size(Pixels::from(u32::from(self.flags.entries_font_size)))
-                ]
-                .spacing(10)
-                .align_y(Alignment::Center);
+                let font_size = u32::from(self.flags.entries_font_size);
+                let mut label =
+                    Column::new().push(text(entry.title.as_str()).size(Pixels::from(font_size)));
+                if let Some(subtitle) = &entry.subtitle {
+                    label =
+                        label.push(text(subtitle.as_str()).size(Pixels::from(font_size * 3 / 4)));
+                }
+                let pin = entry
+                    .pin
+                    .map(|_| text(PIN_MARKER).size(Pixels::from(font_size)));
+                let content = row![icon, label.width(Length::Fill)]
+                    .push(pin)
+                    .spacing(10)
+                    .align_y(Alignment::Center);
 
                 button(content)
-                    .style(if selected { primary } else { text_style })
+                    .style(match (selected, marked) {
+                        (true, _) => primary,
+                        (false, true) => success,
+                        (false, false) => text_style,
+                    })
                     .width(Length::Fill)
                     .on_press(ElbeyMessage::ExecuteSelected())
                     .into()
@@ -182,19 +256,31 @@ impl Elbey {
 
         // Bare bones!
         // TODO: Fancier layout?
-        let content = column![
-            text_input(&self.flags.hint, &self.state.entry)
-                .id(ENTRY_WIDGET_ID.clone())
-                .on_input(ElbeyMessage::EntryUpdate)
-                .size(Pixels::from(u32::from(self.flags.filter_font_size)))
-                .width(Length::Fill),
-            scrollable(Column::with_children(app_elements))
-                .width(Length::Fill)
-                .height(Length::Fill)
-                .id(ITEMS_WIDGET_ID.clone()),
-        ]
-        .width(Length::Fill)
-        .height(Length::Fill);
+        let mut content = Column::new();
+        if self.tabs.len() > 1 {
+            content = content.push(self.tab_indicator());
+        }
+        let content = content
+            .push(
+                text_input(&self.hint(), &self.state.entry)
+                    .id(ENTRY_WIDGET_ID.clone())
+                    .on_input(ElbeyMessage::EntryUpdate)
+                    .size(Pixels::from(u32::from(self.flags.filter_font_size)))
+                    .width(Length::Fill),
+            )
+            .push(self.status().map(|message| {
+                text(message).size(Pixels::from(
+                    u32::from(self.flags.entries_font_size) * 3 / 4,
+                ))
+            }))
+            .push(
+                scrollable(Column::with_children(item_elements))
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .id(ITEMS_WIDGET_ID.clone()),
+            )
+            .width(Length::Fill)
+            .height(Length::Fill);
 
         container(content)
             .width(Length::Fill)
@@ -212,42 +298,76 @@ impl Elbey {
     /// Entry-point from `iced` to handle user and system events
     pub fn update(&mut self, message: ElbeyMessage) -> Task<ElbeyMessage> {
         match message {
-            // The model has been loaded, initialize the UI
-            ElbeyMessage::ModelLoaded(items) => {
-                self.state.apps = items;
-                self.state.entry_lower = self.state.entry.to_lowercase();
-                self.refresh_filtered_indices();
+            // The items of a provider have been loaded, initialize the UI
+            ElbeyMessage::ItemsLoaded(provider, listing) => {
+                if let Some(slot) = self.state.provider_items.get_mut(provider) {
+                    *slot = Some(listing.items);
+                }
+                // Items of other tabs are kept until the user switches to them
+                if !self.tabs[self.state.tab].contains(&provider) {
+                    return Task::none();
+                }
+                if listing.message.is_some() {
+                    self.state.message = listing.message;
+                }
+                if listing.prompt.is_some() {
+                    self.state.prompt = listing.prompt;
+                }
+                self.refresh_items();
                 let focus_task = focus(ENTRY_WIDGET_ID.clone());
                 let refresh_task = Task::perform(async {}, |_| ElbeyMessage::PostLoadRefresh);
                 Task::batch(vec![focus_task, refresh_task])
             }
+            ElbeyMessage::ItemsUpdated(provider, update) => {
+                self.apply_items_update(provider, update);
+                Task::none()
+            }
             // Rebuild the select list based on the updated text entry
             ElbeyMessage::EntryUpdate(entry_text) => {
-                self.state.entry = entry_text;
-                self.state.entry_lower = self.state.entry.to_lowercase();
-                self.state.selected_index = 0;
-                self.refresh_filtered_indices();
+                self.state.history_position = None;
+                self.set_entry(entry_text);
                 Task::none()
             }
-            // Launch an application selected by the user
-            ElbeyMessage::ExecuteSelected() => {
-                if let Some(entry) = self.selected_entry() {
-                    (self.flags.app_launcher)(entry).expect("Failed to launch app");
+            // Act on the item selected by the user
+            ElbeyMessage::ExecuteSelected() => self.execute_selected(),
+            ElbeyMessage::ExecuteAlternate => {
+                let Some((index, entry)) = self.selected_entry() else {
+                    return Task::none();
+                };
+                let provider = self.providers[index].clone();
+                let entry = entry.clone();
+                self.activate(move || vec![(index, provider.activate_alternate(&entry))])
+            }
+            ElbeyMessage::Activated(outcomes) => {
+                self.state.activating = false;
+                for (provider, result) in outcomes {
+                    self.apply_activation(provider, result);
                 }
                 Task::none()
             }
             // Handle keyboard entries
             ElbeyMessage::KeyEvent(key) => match key {
                 Key::Named(Named::Escape) => {
-                    persist_cache_snapshot(&self.state.apps);
+                    self.close_providers();
                     exit(0)
                 }
                 Key::Named(Named::ArrowUp) => {
-                    self.navigate_items(-1);
+                    let at_top = self.state.selected_index == 0;
+                    if at_top
+                        && (self.state.entry.is_empty() || self.state.history_position.is_some())
+                    {
+                        self.recall_history(1);
+                    } else {
+                        self.navigate_items(-1);
+                    }
                     Task::none()
                 }
                 Key::Named(Named::ArrowDown) => {
-                    self.navigate_items(1);
+                    if self.state.history_position.is_some() {
+                        self.recall_history(-1);
+                    } else {
+                        self.navigate_items(1);
+                    }
                     Task::none()
                 }
                 Key::Named(Named::PageUp) => {
@@ -258,11 +378,19 @@ impl Elbey {
                     self.navigate_items(VIEWABLE_LIST_ITEM_COUNT as i32);
                     Task::none()
                 }
-                Key::Named(Named::Enter) => {
-                    if let Some(entry) = self.selected_entry() {
-                        (self.flags.app_launcher)(entry).expect("Failed to launch app");
+                Key::Named(Named::Enter) => self.execute_selected(),
+                Key::Named(Named::Tab) => {
+                    let completion = self
+                        .selected_entry()
+                        .and_then(|(provider, entry)| self.providers[provider].complete(entry));
+                    match completion {
+                        Some(completion) => {
+                            self.state.history_position = None;
+                            self.set_entry(completion);
+                            move_cursor_to_end(ENTRY_WIDGET_ID.clone())
+                        }
+                        None => Task::none(),
                     }
-                    Task::none()
                 }
                 _ => Task::none(),
             },
@@ -273,12 +401,75 @@ impl Elbey {
             }
             ElbeyMessage::LostFocus => {
                 if self.state.received_focus {
-                    persist_cache_snapshot(&self.state.apps);
+                    self.close_providers();
                     exit(0);
                 }
                 Task::none()
             }
             ElbeyMessage::PostLoadRefresh => Task::none(),
+            ElbeyMessage::SwitchTab => {
+                if self.tabs.len() < 2 {
+                    return Task::none();
+                }
+                self.select_tab((self.state.tab + 1) % self.tabs.len())
+            }
+            ElbeyMessage::SelectTab(tab) => {
+                if tab >= self.tabs.len() || tab == self.state.tab {
+                    return Task::none();
+                }
+                self.select_tab(tab)
+            }
+            ElbeyMessage::ToggleMark => {
+                if !self.flags.multi_select {
+                    return self.execute_selected();
+                }
+                if let Some(item) = self.selected_item_position() {
+                    match self.state.marked.iter().position(|marked| *marked == item) {
+                        Some(position) => {
+                            self.state.marked.remove(position);
+                        }
+                        None => self.state.marked.push(item),
+                    }
+                    self.navigate_items(1);
+                }
+                Task::none()
+            }
+            ElbeyMessage::TogglePin => {
+                let pins = self.selected_entry().and_then(|(provider, entry)| {
+                    Some((provider, self.providers[provider].toggle_pin(entry)?))
+                });
+                if let Some((provider, result)) = pins {
+                    self.apply_pins(provider, result);
+                }
+                Task::none()
+            }
+            ElbeyMessage::MovePin(delta) => {
+                let pins = self.selected_entry().and_then(|(provider, entry)| {
+                    Some((provider, self.providers[provider].move_pin(entry, delta)?))
+                });
+                if let Some((provider, result)) = pins {
+                    self.apply_pins(provider, result);
+                }
+                Task::none()
+            }
+            ElbeyMessage::HideSelected => {
+                let hidden = self.selected_entry().and_then(|(provider, entry)| {
+                    let result = self.providers[provider].hide(entry)?;
+                    Some((provider, entry.id.clone(), result))
+                });
+                match hidden {
+                    Some((provider, id, Ok(()))) => {
+                        let update = ItemsUpdate {
+                            updated: Vec::new(),
+                            removed: vec![id],
+                        };
+                        self.apply_items_update(provider, update);
+                    }
+                    Some((_, _, Err(err))) => self.state.message = Some(format!("{err:#}")),
+                    None => {}
+                }
+                Task::none()
+            }
             ElbeyMessage::AnchorChange(anchor) => {
                 dbg!(anchor);
                 Task::none()
@@ -314,8 +505,43 @@ impl Elbey {
     /// The `iced` entry-point to setup event listeners
     pub fn subscription(&self) -> iced::Subscription<ElbeyMessage> {
         // Framework code to integrate with underlying user interface devices; keyboard, mouse.
-        event::listen_with(|event, _status, _| match event {
+        let events = event::listen_with(|event, _status, _| match event {
             Event::Window(window::Event::Focused) => Some(ElbeyMessage::GainedFocus),
+            Event::Keyboard(iced::keyboard::Event::KeyPressed {
+                key: Key::Named(Named::Tab),
+                modifiers,
+                ..
+            }) if modifiers.control() => Some(ElbeyMessage::SwitchTab),
+            Event::Keyboard(iced::keyboard::Event::KeyPressed {
+                key: Key::Named(Named::Enter),
+                modifiers,
+                ..
+            }) if modifiers.shift() => Some(ElbeyMessage::ToggleMark),
+            Event::Keyboard(iced::keyboard::Event::KeyPressed {
+                key: Key::Named(Named::Enter),
+                modifiers,
+                ..
+            }) if modifiers.control() => Some(ElbeyMessage::ExecuteAlternate),
+            Event::Keyboard(iced::keyboard::Event::KeyPressed {
+                key: Key::Character(c),
+                modifiers,
+                ..
+            }) if modifiers.control() && c.as_str() == "p" => Some(ElbeyMessage::TogglePin),
+            Event::Keyboard(iced::keyboard::Event::KeyPressed {
+                key: Key::Character(c),
+                modifiers,
+                ..
+            }) if modifiers.control() && c.as_str() == "h" => Some(ElbeyMessage::HideSelected),
+            Event::Keyboard(iced::keyboard::Event::KeyPressed {
+                key: Key::Named(Named::ArrowUp),
+                modifiers,
+                ..
+            }) if modifiers.control() => Some(ElbeyMessage::MovePin(-1)),
+            Event::Keyboard(iced::keyboard::Event::KeyPressed {
+                key: Key::Named(Named::ArrowDown),
+                modifiers,
+                ..
+            }) if modifiers.control() => Some(ElbeyMessage::MovePin(1)),
             Event::Window(window::Event::Unfocused) => Some(ElbeyMessage::LostFocus),
             Event::Keyboard(iced::keyboard::Event::KeyPressed {
                 modifiers: _,
@@ -327,25 +553,360 @@ impl Elbey {
                 repeat: _,
             }) => Some(ElbeyMessage::KeyEvent(key)),
             _ => None,
-        })
+        });
+
+        let updates = self
+            .providers
+            .iter()
+            .enumerate()
+            .filter_map(|(index, provider)| {
+                provider.updates().map(|updates| {
+                    updates
+                        .with(index)
+                        .map(|(index, update)| ElbeyMessage::ItemsUpdated(index, update))
+                })
+            });
+
+        Subscription::batch(std::iter::once(events).chain(updates))
     }
 
     pub fn theme(&self) -> Theme {
         self.flags.theme.clone()
     }
+
+    // A tab is named after its providers
+    fn tab_name(&self, tab: usize) -> String {
+        self.tabs[tab]
+            .iter()
+            .map(|provider| self.providers[*provider].name())
+            .collect::<Vec<_>>()
+            .join("+")
+    }
+
+    // The default hint names the active tab
+    fn hint(&self) -> String {
+        if let Some(prompt) = &self.state.prompt {
+            prompt.clone()
+        } else if self.flags.hint == DEFAULT_HINT && !self.tabs.is_empty() {
+            self.tab_name(self.state.tab)
+        } else {
+            self.flags.hint.clone()
+        }
+    }
+
+    // The line shown above the list: a busy notice while a provider acts on a choice, else the
+    // provider's message
+    fn status(&self) -> Option<&str> {
+        if self.state.activating {
+            Some(BUSY_MESSAGE)
+        } else {
+            self.state.message.as_deref()
+        }
+    }
+
+    // A row of tab names with the active one highlighted
+    fn tab_indicator(&self) -> Element<'_, ElbeyMessage> {
+        let font_size = Pixels::from(u32::from(self.flags.entries_font_size) * 3 / 4);
+        let tabs = (0..self.tabs.len()).map(|tab| {
+            button(text(self.tab_name(tab)).size(font_size))
+                .style(if tab == self.state.tab {
+                    primary
+                } else {
+                    text_style
+                })
+                .on_press(ElbeyMessage::SelectTab(tab))
+                .into()
+        });
+        Row::with_children(tabs).spacing(4).into()
+    }
 }
 
 impl Elbey {
-    // Return ref to the selected item from the app list after applying filter
-    fn selected_entry(&self) -> Option<&AppDescriptor> {
-        self.state
-            .filtered_indices
-            .get(self.state.selected_index)
-            .and_then(|original_index| self.state.apps.get(*original_index))
+    // Show the tab at index `tab`, loading its providers' items if needed
+    fn select_tab(&mut self, tab: usize) -> Task<ElbeyMessage> {
+        self.state.tab = tab;
+        self.state.selected_index = 0;
+        self.state.marked.clear();
+        self.state.message = None;
+        self.state.prompt = None;
+        self.refresh_items();
+        Task::batch(vec![self.load_tab(), focus(ENTRY_WIDGET_ID.clone())])
+    }
+
+    // Ask the providers of the active tab that have not loaded yet for their items
+    fn load_tab(&self) -> Task<ElbeyMessage> {
+        let Some(tab) = self.tabs.get(self.state.tab) else {
+            return Task::none();
+        };
+        let tasks: Vec<Task<ElbeyMessage>> = tab
+            .iter()
+            .filter(|index| self.state.provider_items[**index].is_none())
+            .map(|index| {
+                let index = *index;
+                let provider = self.providers[index].clone();
+                Task::perform(async move { provider.load() }, move |items| {
+                    ElbeyMessage::ItemsLoaded(index, items)
+                })
+            })
+            .collect();
+        Task::batch(tasks)
+    }
+
+    fn close_providers(&self) {
+        for (provider, items) in self.providers.iter().zip(&self.state.provider_items) {
+            if let Some(items) = items {
+                provider.close(items);
+            }
+        }
+    }
+
+    fn execute_selected(&mut self) -> Task<ElbeyMessage> {
+        if !self.state.marked.is_empty() {
+            return self.execute_marked();
+        }
+
+        // The provider of the selection decides whether the query or the selection is acted on
+        let query = self.state.entry.clone();
+        if let Some((index, entry)) = self.selected_entry() {
+            let provider = self.providers[index].clone();
+            let entry = entry.clone();
+            return self.activate(move || {
+                let result = provider
+                    .activate_query(&query, Some(&entry))
+                    .unwrap_or_else(|| provider.activate(&entry));
+                vec![(index, result)]
+            });
+        }
+        let tab: Vec<(usize, Arc<dyn Provider>)> = self
+            .tabs
+            .get(self.state.tab)
+            .into_iter()
+            .flatten()
+            .map(|index| (*index, self.providers[*index].clone()))
+            .collect();
+        self.activate(move || {
+            tab.into_iter()
+                .find_map(|(index, provider)| {
+                    provider
+                        .activate_query(&query, None)
+                        .map(|result| (index, result))
+                })
+                .into_iter()
+                .collect()
+        })
+    }
+
+    // Run an activation off the UI thread, as providers may block on the programs they run
+    fn activate(
+        &mut self,
+        activation: impl FnOnce() -> Vec<(usize, anyhow::Result<Activation>)> + Send + 'static,
+    ) -> Task<ElbeyMessage> {
+        if self.state.activating {
+            return Task::none();
+        }
+        self.state.activating = true;
+        Task::perform(async move { activation() }, |outcomes| {
+            let outcomes = outcomes
+                .into_iter()
+                .map(|(provider, result)| (provider, result.map_err(|err| format!("{err:#}"))))
+                .collect();
+            ElbeyMessage::Activated(outcomes)
+        })
+    }
+
+    // Hand the marked items to their providers, each provider getting its items in marked order
+    fn execute_marked(&mut self) -> Task<ElbeyMessage> {
+        let mut providers: Vec<usize> = Vec::new();
+        for (provider, _) in &self.state.marked {
+            if !providers.contains(provider) {
+                providers.push(*provider);
+            }
+        }
+        let batches: Vec<(usize, Arc<dyn Provider>, Vec<Item>)> = providers
+            .into_iter()
+            .map(|provider| {
+                let items = self
+                    .state
+                    .marked
+                    .iter()
+                    .filter(|(marked_provider, _)| *marked_provider == provider)
+                    .filter_map(|(_, index)| {
+                        self.state.provider_items[provider].as_ref()?.get(*index)
+                    })
+                    .cloned()
+                    .collect();
+                (provider, self.providers[provider].clone(), items)
+            })
+            .collect();
+        self.activate(move || {
+            batches
+                .into_iter()
+                .map(|(index, provider, items)| {
+                    let items: Vec<&Item> = items.iter().collect();
+                    (index, provider.activate_all(&items))
+                })
+                .collect()
+        })
+    }
+
+    // Show what came of acting on a choice: a new listing, or the error
+    fn apply_activation(&mut self, provider: usize, result: Result<Activation, String>) {
+        match result {
+            Ok(Activation::Done) => {}
+            Ok(Activation::Replace(listing)) => {
+                self.state.provider_items[provider] = Some(listing.items);
+                self.state.message = listing.message;
+                self.state.prompt = listing.prompt;
+                self.state.marked.clear();
+                self.state.history_position = None;
+                self.state.entry.clear();
+                self.state.entry_lower.clear();
+                self.state.selected_index = 0;
+                self.refresh_items();
+            }
+            Err(err) => self.state.message = Some(err),
+        }
+    }
+
+    fn item(&self, item_index: usize) -> Option<&Item> {
+        let (provider, index) = self.state.items.get(item_index)?;
+        self.state.provider_items[*provider].as_ref()?.get(*index)
+    }
+
+    // The number of rows listed: query items, the loaded items that match the filter, then
+    // fallback items
+    fn row_count(&self) -> usize {
+        self.state.query_items.len()
+            + self.state.filtered_indices.len()
+            + self.state.fallback_items.len()
+    }
+
+    // Return the item in a row, and the index of its provider
+    fn row(&self, row: usize) -> Option<(usize, &Item)> {
+        if let Some((provider, item)) = self.state.query_items.get(row) {
+            return Some((*provider, item));
+        }
+        let filtered_row = row - self.state.query_items.len();
+        let Some(item_index) = self.state.filtered_indices.get(filtered_row) else {
+            let fallback_row = filtered_row - self.state.filtered_indices.len();
+            let (provider, item) = self.state.fallback_items.get(fallback_row)?;
+            return Some((*provider, item));
+        };
+        let (provider, _) = self.state.items.get(*item_index)?;
+        self.item(*item_index).map(|item| (*provider, item))
+    }
+
+    // Return the (provider index, item index) pair of a row listing a loaded item
+    fn row_position(&self, row: usize) -> Option<(usize, usize)> {
+        let filtered_index = row.checked_sub(self.state.query_items.len())?;
+        let item_index = *self.state.filtered_indices.get(filtered_index)?;
+        self.state.items.get(item_index).copied()
+    }
+
+    // Return the (provider index, item index) pair of the selected item after applying filter
+    fn selected_item_position(&self) -> Option<(usize, usize)> {
+        self.row_position(self.state.selected_index)
+    }
+
+    // Return the selected item, and the index of its provider, after applying filter
+    fn selected_entry(&self) -> Option<(usize, &Item)> {
+        self.row(self.state.selected_index)
+    }
+
+    // Patch the items of a provider, keeping the current selection where possible
+    fn apply_items_update(&mut self, provider: usize, update: ItemsUpdate) {
+        let selected = self
+            .selected_entry()
+            .map(|(provider, item)| (provider, item.id.clone()));
+        let Some(Some(items)) = self.state.provider_items.get_mut(provider) else {
+            return;
+        };
+
+        items.retain(|item| !update.removed.contains(&item.id));
+        for item in update.updated {
+            match items.iter_mut().find(|existing| existing.id == item.id) {
+                // A changed item stays pinned
+                Some(existing) => *existing = item.with_pin(existing.pin),
+                None => items.push(item),
+            }
+        }
+        sort_items(items);
+        self.reorder_items(provider, selected);
+    }
+
+    /// Mark the items of `provider` with their position in `pins`, the pinned ids in order, and
+    /// re-sort them, or show why the pins could not be changed.
+    fn apply_pins(&mut self, provider: usize, pins: anyhow::Result<Vec<String>>) {
+        let pins = match pins {
+            Ok(pins) => pins,
+            Err(err) => {
+                self.state.message = Some(err.to_string());
+                return;
+            }
+        };
+        let selected = self
+            .selected_entry()
+            .map(|(provider, item)| (provider, item.id.clone()));
+        let Some(Some(items)) = self.state.provider_items.get_mut(provider) else {
+            return;
+        };
+        for item in items.iter_mut() {
+            item.pin = pins.iter().position(|pin| *pin == item.id);
+        }
+        sort_items(items);
+        self.reorder_items(provider, selected);
+    }
+
+    /// Rebuild the rows after the items of `provider` were re-sorted, keeping `selected` selected.
+    fn reorder_items(&mut self, provider: usize, selected: Option<(usize, String)>) {
+        // Item indices of the provider have shifted
+        self.state.marked.retain(|(marked, _)| *marked != provider);
+        self.refresh_items();
+
+        if let Some((selected_provider, id)) = selected {
+            if let Some(row) = (0..self.row_count()).find(|row| {
+                self.row(*row)
+                    .is_some_and(|(provider, item)| provider == selected_provider && item.id == id)
+            }) {
+                self.state.selected_index = row;
+            }
+        }
+    }
+
+    fn set_entry(&mut self, entry: String) {
+        self.state.entry = entry;
+        self.state.entry_lower = self.state.entry.to_lowercase();
+        self.state.selected_index = 0;
+        self.refresh_filtered_indices();
+    }
+
+    // Step through the queries entered before: 1 recalls an older one, -1 a newer one, and
+    // stepping past the newest clears the query
+    fn recall_history(&mut self, delta: i32) {
+        let providers = self.tabs[self.state.tab].iter().chain(&self.fallbacks);
+        let mut history: Vec<String> = Vec::new();
+        for entry in providers.flat_map(|provider| self.providers[*provider].history()) {
+            if !history.contains(&entry) {
+                history.push(entry);
+            }
+        }
+
+        let position = match self.state.history_position {
+            None if delta > 0 => 0,
+            None => return,
+            Some(position) => position as i32 + delta,
+        };
+        if position < 0 {
+            self.state.history_position = None;
+            self.set_entry(String::new());
+        } else if let Some(entry) = history.get(position as usize) {
+            self.state.history_position = Some(position as usize);
+            self.set_entry(entry.clone());
+        }
     }
 
     fn navigate_items(&mut self, delta: i32) {
-        let filtered_len = self.state.filtered_indices.len();
+        let filtered_len = self.row_count();
         if filtered_len == 0 {
             self.state.selected_index = 0;
             return;
@@ -361,32 +922,110 @@ impl Elbey {
         }
     }
 
-    // Compute the items in the list to display based on the model
-    fn text_entry_filter(entry: &AppDescriptor, model: &State) -> bool {
-        entry.lower_title.contains(&model.entry_lower)
+    // List the loaded items of the providers in the active tab, in provider order
+    fn refresh_items(&mut self) {
+        let provider_items = &self.state.provider_items;
+        self.state.items = self
+            .tabs
+            .get(self.state.tab)
+            .into_iter()
+            .flatten()
+            .flat_map(|provider| {
+                let count = provider_items[*provider].as_ref().map_or(0, Vec::len);
+                (0..count).map(move |index| (*provider, index))
+            })
+            .collect();
+        self.refresh_filtered_indices();
     }
 
     fn refresh_filtered_indices(&mut self) {
-        self.state.filtered_indices = self
-            .state
-            .apps
+        // A query addressed to one provider lists only that provider's items
+        if let Some(provider) = self
+            .exclusive
             .iter()
-            .enumerate()
-            .filter(|(_, e)| Self::text_entry_filter(e, &self.state))
-            .map(|(i, _)| i)
+            .find(|provider| self.providers[**provider].owns_query(&self.state.entry))
+        {
+            self.state.query_items = self.providers[*provider]
+                .query(&self.state.entry)
+                .into_iter()
+                .map(|item| (*provider, item))
+                .collect();
+            self.state.filtered_indices.clear();
+            self.state.fallback_items.clear();
+            if self.state.selected_index >= self.row_count() {
+                self.state.selected_index = self.row_count().saturating_sub(1);
+            }
+            return;
+        }
+
+        let tab = self.tabs.get(self.state.tab).into_iter().flatten();
+        self.state.query_items = tab
+            .flat_map(|provider| {
+                self.providers[*provider]
+                    .query(&self.state.entry)
+                    .into_iter()
+                    .map(|item| (*provider, item))
+            })
             .collect();
 
-        if self.state.selected_index >= self.state.filtered_indices.len() {
-            self.state.selected_index = self.state.filtered_indices.len().saturating_sub(1);
+        self.state.filtered_indices = (0..self.state.items.len())
+            .filter(|index| {
+                self.item(*index).is_some_and(|item| {
+                    if self.flags.case_sensitive {
+                        item.matches_case_sensitive(&self.state.entry)
+                    } else {
+                        item.matches(&self.state.entry_lower)
+                    }
+                })
+            })
+            .collect();
+
+        // Fallbacks are offered for a query that matches nothing
+        let matched = !self.state.query_items.is_empty() || !self.state.filtered_indices.is_empty();
+        self.state.fallback_items = if matched || self.state.entry.trim().is_empty() {
+            Vec::new()
+        } else {
+            self.fallbacks
+                .iter()
+                .flat_map(|provider| {
+                    self.providers[*provider]
+                        .query(&self.state.entry)
+                        .into_iter()
+                        .map(|item| (*provider, item))
+                })
+                .collect()
+        };
+
+        if self.state.selected_index >= self.row_count() {
+            self.state.selected_index = self.row_count().saturating_sub(1);
         }
     }
 }
 
+/// Sort pinned items first in pin order, then the others by rank and title.
+fn sort_items(items: &mut [Item]) {
+    items.sort_by(|a, b| {
+        (a.pin.is_none(), a.pin, b.rank, &a.title).cmp(&(b.pin.is_none(), b.pin, a.rank, &b.title))
+    });
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::output::Output;
+    use crate::providers::apps::{app_item, AppsProvider};
+    use crate::providers::calc::CalcProvider;
+    use crate::providers::files::FilesProvider;
+    use crate::providers::path::PathProvider;
+    use crate::providers::run::RunProvider;
+    use crate::providers::script::ScriptProvider;
+    use crate::providers::shell::ShellProvider;
+    use crate::providers::web::{bangs, WebProvider};
+    use crate::terminal::Terminal;
+    use elbey_cache::AppDescriptor;
     use std::path::PathBuf;
-    use std::sync::{LazyLock, OnceLock};
+    use std::sync::{LazyLock, Mutex, OnceLock};
+    use std::time::Duration;
 
     fn set_test_cache_home() {
         static CACHE_HOME: OnceLock<PathBuf> = OnceLock::new();
@@ -442,25 +1081,64 @@ mod tests {
         ]
     };
 
-    #[test]
-    fn test_default_app_launch() {
-        let test_launcher: fn(&AppDescriptor) -> anyhow::Result<()> = |e| {
-            assert!(e.appid == "test_app_id_1");
-            Ok(())
+    fn test_items() -> Listing {
+        TEST_ENTRY_LOADER()
+            .into_iter()
+            .map(app_item)
+            .collect::<Vec<_>>()
+            .into()
+    }
+
+    fn apps_provider(launcher: fn(&AppDescriptor) -> anyhow::Result<()>) -> Arc<dyn Provider> {
+        Arc::new(AppsProvider::new(TEST_ENTRY_LOADER, launcher, None))
+    }
+
+    // Apply `message`, then the messages its tasks produce, such as the outcome of an activation
+    fn settle(unit: &mut Elbey, message: ElbeyMessage) {
+        let task = unit.update(message);
+        finish(unit, task);
+    }
+
+    // Apply the messages produced by `task`
+    fn finish(unit: &mut Elbey, task: Task<ElbeyMessage>) {
+        let Some(stream) = iced_runtime::task::into_stream(task) else {
+            return;
         };
+        for action in
+            iced::futures::executor::block_on(iced::futures::StreamExt::collect::<Vec<_>>(stream))
+        {
+            if let iced_runtime::Action::Output(message) = action {
+                settle(unit, message);
+            }
+        }
+    }
 
-        let (mut unit, _) = Elbey::new(ElbeyFlags {
-            apps_loader: TEST_ENTRY_LOADER,
-            app_launcher: test_launcher,
+    fn test_flags(tabs: Vec<Vec<Arc<dyn Provider>>>) -> ElbeyFlags {
+        ElbeyFlags {
+            tabs,
             theme: DEFAULT_THEME,
             icon_size: 48,
             hint: DEFAULT_HINT.to_string(),
             filter_font_size: DEFAULT_TEXT_SIZE,
             entries_font_size: DEFAULT_TEXT_SIZE,
-        });
+            case_sensitive: false,
+            multi_select: false,
+            fallbacks: vec![],
+            exclusive: vec![],
+        }
+    }
+
+    #[test]
+    fn test_default_app_launch() {
+        let test_launcher: fn(&AppDescriptor) -> anyhow::Result<()> = |e| {
+            assert!(e.appid == "test_app_id_1");
+            Ok(())
+        };
+
+        let (mut unit, _) = Elbey::new(test_flags(vec![vec![apps_provider(test_launcher)]]));
 
-        let _ = unit.update(ElbeyMessage::ModelLoaded(TEST_ENTRY_LOADER()));
-        let _ = unit.update(ElbeyMessage::ExecuteSelected());
+        let _ = unit.update(ElbeyMessage::ItemsLoaded(0, test_items()));
+        settle(&mut unit, ElbeyMessage::ExecuteSelected());
     }
 
     #[test]
@@ -469,18 +1147,10 @@ mod tests {
             unreachable!("should never get here");
         };
 
-        let (mut unit, _) = Elbey::new(ElbeyFlags {
-            apps_loader: TEST_ENTRY_LOADER,
-            app_launcher: test_launcher,
-            theme: DEFAULT_THEME,
-            icon_size: 48,
-            hint: DEFAULT_HINT.to_string(),
-            filter_font_size: DEFAULT_TEXT_SIZE,
-            entries_font_size: DEFAULT_TEXT_SIZE,
-        });
+        let (mut unit, _) = Elbey::new(test_flags(vec![vec![apps_provider(test_launcher)]]));
 
-        let _ = unit.update(ElbeyMessage::ModelLoaded(EMPTY_LOADER()));
-        let _result = unit.update(ElbeyMessage::ExecuteSelected());
+        let _ = unit.update(ElbeyMessage::ItemsLoaded(0, Listing::default()));
+        settle(&mut unit, ElbeyMessage::ExecuteSelected());
     }
 
     #[test]
@@ -490,40 +1160,411 @@ mod tests {
             Ok(())
         };
 
-        let (mut unit, _) = Elbey::new(ElbeyFlags {
-            apps_loader: TEST_ENTRY_LOADER,
-            app_launcher: test_launcher,
-            theme: DEFAULT_THEME,
-            icon_size: 48,
-            hint: DEFAULT_HINT.to_string(),
-            filter_font_size: DEFAULT_TEXT_SIZE,
-            entries_font_size: DEFAULT_TEXT_SIZE,
-        });
+        let (mut unit, _) = Elbey::new(test_flags(vec![vec![apps_provider(test_launcher)]]));
 
-        let _ = unit.update(ElbeyMessage::ModelLoaded(TEST_ENTRY_LOADER()));
+        let _ = unit.update(ElbeyMessage::ItemsLoaded(0, test_items()));
         let _ = unit.update(ElbeyMessage::KeyEvent(Key::Named(Named::ArrowDown)));
         let _ = unit.update(ElbeyMessage::KeyEvent(Key::Named(Named::ArrowDown)));
         let _ = unit.update(ElbeyMessage::KeyEvent(Key::Named(Named::ArrowUp)));
-        let _ = unit.update(ElbeyMessage::ExecuteSelected());
+        settle(&mut unit, ElbeyMessage::ExecuteSelected());
     }
 
     #[test]
     fn test_loaded_icons_render_immediately() {
         set_test_cache_home();
-        let (mut unit, _) = Elbey::new(ElbeyFlags {
-            apps_loader: TEST_ENTRY_LOADER,
-            app_launcher: |_| Ok(()),
-            theme: DEFAULT_THEME,
-            icon_size: 48,
-            hint: DEFAULT_HINT.to_string(),
-            filter_font_size: DEFAULT_TEXT_SIZE,
-            entries_font_size: DEFAULT_TEXT_SIZE,
-        });
-        let _ = unit.update(ElbeyMessage::ModelLoaded(TEST_ENTRY_LOADER()));
+        let (mut unit, _) = Elbey::new(test_flags(vec![vec![apps_provider(|_| Ok(()))]]));
+        let _ = unit.update(ElbeyMessage::ItemsLoaded(0, test_items()));
 
         assert!(matches!(
-            unit.state.apps[0].icon_handle,
+            unit.item(0).unwrap().icon,
             IconHandle::Vector(_) | IconHandle::Raster(_) | IconHandle::NotLoaded
         ));
     }
+
+    #[test]
+    fn test_pinned_items_list_first_in_pin_order() {
+        static PINS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+        let apps = AppsProvider::new(TEST_ENTRY_LOADER, |_| Ok(()), None).with_pins(
+            |_| PINS.lock().unwrap().clone(),
+            |_, pins| {
+                *PINS.lock().unwrap() = pins.to_vec();
+                Ok(())
+            },
+        );
+        let (mut unit, _) = Elbey::new(test_flags(vec![vec![Arc::new(apps)]]));
+        let _ = unit.update(ElbeyMessage::ItemsLoaded(0, test_items()));
+        let ids = |unit: &Elbey| -> Vec<String> {
+            (0..unit.row_count())
+                .map(|row| unit.row(row).unwrap().1.id.clone())
+                .collect()
+        };
+
+        let _ = unit.update(ElbeyMessage::KeyEvent(Key::Named(Named::ArrowDown)));
+        let _ = unit.update(ElbeyMessage::KeyEvent(Key::Named(Named::ArrowDown)));
+        let _ = unit.update(ElbeyMessage::TogglePin);
+        let _ = unit.update(ElbeyMessage::KeyEvent(Key::Named(Named::ArrowDown)));
+        let _ = unit.update(ElbeyMessage::KeyEvent(Key::Named(Named::ArrowDown)));
+        let _ = unit.update(ElbeyMessage::TogglePin);
+        assert_eq!(
+            ids(&unit),
+            vec!["test_app_id_3", "test_app_id_2", "test_app_id_1"]
+        );
+        assert_eq!(unit.selected_entry().unwrap().1.id, "test_app_id_2");
+
+        let _ = unit.update(ElbeyMessage::MovePin(-1));
+        assert_eq!(
+            *PINS.lock().unwrap(),
+            vec!["test_app_id_2", "test_app_id_3"]
+        );
+        assert_eq!(unit.state.selected_index, 0);
+
+        // Pins are read back when the list is loaded again
+        let listing = unit.providers[0].load();
+        assert_eq!(listing.items[0].id, "test_app_id_2");
+        assert_eq!(listing.items[1].pin, Some(1));
+
+        let _ = unit.update(ElbeyMessage::TogglePin);
+        assert_eq!(
+            ids(&unit),
+            vec!["test_app_id_3", "test_app_id_1", "test_app_id_2"]
+        );
+    }
+
+    #[test]
+    fn test_hidden_item_leaves_the_list() {
+        static HIDDEN: Mutex<Vec<String>> = Mutex::new(Vec::new());
+        let apps = AppsProvider::new(TEST_ENTRY_LOADER, |_| Ok(()), None).with_hider(|app| {
+            HIDDEN.lock().unwrap().push(app.appid.clone());
+            Ok(())
+        });
+        let (mut unit, _) = Elbey::new(test_flags(vec![vec![Arc::new(apps)]]));
+        let _ = unit.update(ElbeyMessage::ItemsLoaded(0, test_items()));
+
+        let _ = unit.update(ElbeyMessage::KeyEvent(Key::Named(Named::ArrowDown)));
+        let _ = unit.update(ElbeyMessage::HideSelected);
+        assert_eq!(*HIDDEN.lock().unwrap(), vec!["test_app_id_2"]);
+        assert_eq!(unit.row_count(), 2);
+        // The next item takes the hidden item's place
+        assert_eq!(unit.selected_entry().unwrap().1.id, "test_app_id_3");
+
+        // Providers that cannot hide items keep them
+        let (mut unit, _) = Elbey::new(test_flags(vec![vec![apps_provider(|_| Ok(()))]]));
+        let _ = unit.update(ElbeyMessage::ItemsLoaded(0, test_items()));
+        let _ = unit.update(ElbeyMessage::HideSelected);
+        assert_eq!(unit.row_count(), 3);
+    }
+
+    #[test]
+    fn test_items_update_patches_list_and_keeps_selection() {
+        let (mut unit, _) = Elbey::new(test_flags(vec![vec![apps_provider(|_| Ok(()))]]));
+        let _ = unit.update(ElbeyMessage::ItemsLoaded(0, test_items()));
+        let _ = unit.update(ElbeyMessage::KeyEvent(Key::Named(Named::ArrowDown)));
+
+        let renamed = AppDescriptor {
+            title: "a0".to_string(),
+            lower_title: "a0".to_string(),
+            ..TEST_DESKTOP_ENTRY_3.clone()
+        };
+        let _ = unit.update(ElbeyMessage::ItemsUpdated(
+            0,
+            ItemsUpdate {
+                updated: vec![app_item(renamed)],
+                removed: vec!["test_app_id_1".to_string()],
+            },
+        ));
+
+        let titles: Vec<&str> = (0..unit.state.items.len())
+            .map(|index| unit.item(index).unwrap().title.as_str())
+            .collect();
+        assert_eq!(titles, vec!["a0", "t2"]);
+        assert_eq!(unit.selected_entry().unwrap().1.id, "test_app_id_2");
+    }
+
+    #[test]
+    fn test_run_mode_runs_typed_command_with_args() {
+        let run_launcher: fn(&AppDescriptor) -> anyhow::Result<()> = |e| {
+            assert_eq!(e.appid, "t2");
+            assert_eq!(e.exec.as_deref(), Some("t2 --verbose"));
+            Ok(())
+        };
+        let run: Arc<dyn Provider> = Arc::new(RunProvider::new(TEST_ENTRY_LOADER, run_launcher));
+
+        let (mut unit, _) = Elbey::new(test_flags(vec![vec![run]]));
+
+        let _ = unit.update(ElbeyMessage::ItemsLoaded(0, test_items()));
+        let _ = unit.update(ElbeyMessage::EntryUpdate("t2 --verbose".to_string()));
+        assert_eq!(unit.hint(), "run");
+        settle(&mut unit, ElbeyMessage::ExecuteSelected());
+    }
+
+    #[test]
+    fn test_case_sensitive_filter() {
+        let mut flags = test_flags(vec![vec![apps_provider(|_| Ok(()))]]);
+        flags.case_sensitive = true;
+        let (mut unit, _) = Elbey::new(flags);
+        let _ = unit.update(ElbeyMessage::ItemsLoaded(0, test_items()));
+
+        let _ = unit.update(ElbeyMessage::EntryUpdate("T".to_string()));
+        assert!(unit.state.filtered_indices.is_empty());
+        let _ = unit.update(ElbeyMessage::EntryUpdate("t".to_string()));
+        assert_eq!(unit.state.filtered_indices.len(), 3);
+    }
+
+    #[test]
+    fn test_multi_select_activates_marked_items_in_order() {
+        static LAUNCHED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+        let mut flags = test_flags(vec![vec![apps_provider(|e| {
+            LAUNCHED.lock().unwrap().push(e.appid.clone());
+            Ok(())
+        })]]);
+        flags.multi_select = true;
+        let (mut unit, _) = Elbey::new(flags);
+        let _ = unit.update(ElbeyMessage::ItemsLoaded(0, test_items()));
+
+        let _ = unit.update(ElbeyMessage::KeyEvent(Key::Named(Named::ArrowDown)));
+        let _ = unit.update(ElbeyMessage::ToggleMark);
+        let _ = unit.update(ElbeyMessage::KeyEvent(Key::Named(Named::ArrowUp)));
+        let _ = unit.update(ElbeyMessage::KeyEvent(Key::Named(Named::ArrowUp)));
+        let _ = unit.update(ElbeyMessage::ToggleMark);
+        settle(&mut unit, ElbeyMessage::ExecuteSelected());
+
+        assert_eq!(
+            *LAUNCHED.lock().unwrap(),
+            vec!["test_app_id_2".to_string(), "test_app_id_1".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_marks_alternating_between_providers_activate_each_once() {
+        static LAUNCHED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+        let launcher: fn(&AppDescriptor) -> anyhow::Result<()> = |e| {
+            LAUNCHED.lock().unwrap().push(e.appid.clone());
+            Ok(())
+        };
+        let run: Arc<dyn Provider> = Arc::new(RunProvider::new(TEST_ENTRY_LOADER, launcher));
+        let mut flags = test_flags(vec![vec![apps_provider(launcher), run]]);
+        flags.multi_select = true;
+        let (mut unit, _) = Elbey::new(flags);
+        let _ = unit.update(ElbeyMessage::ItemsLoaded(0, test_items()));
+        let _ = unit.update(ElbeyMessage::ItemsLoaded(1, test_items()));
+
+        // Mark an app, a command, then another app
+        let _ = unit.update(ElbeyMessage::ToggleMark);
+        let _ = unit.update(ElbeyMessage::KeyEvent(Key::Named(Named::ArrowDown)));
+        let _ = unit.update(ElbeyMessage::KeyEvent(Key::Named(Named::ArrowDown)));
+        let _ = unit.update(ElbeyMessage::ToggleMark);
+        for _ in 0..3 {
+            let _ = unit.update(ElbeyMessage::KeyEvent(Key::Named(Named::ArrowUp)));
+        }
+        let _ = unit.update(ElbeyMessage::ToggleMark);
+        assert_eq!(unit.state.marked, vec![(0, 0), (1, 0), (0, 1)]);
+        settle(&mut unit, ElbeyMessage::ExecuteSelected());
+
+        assert_eq!(
+            *LAUNCHED.lock().unwrap(),
+            vec!["test_app_id_1", "test_app_id_2", "test_app_id_1"]
+        );
+    }
+
+    #[test]
+    fn test_activation_runs_as_a_task_and_shows_busy_state() {
+        static LAUNCHED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+        let (mut unit, _) = Elbey::new(test_flags(vec![vec![apps_provider(|e| {
+            LAUNCHED.lock().unwrap().push(e.appid.clone());
+            Ok(())
+        })]]));
+        let _ = unit.update(ElbeyMessage::ItemsLoaded(0, test_items()));
+
+        let task = unit.update(ElbeyMessage::ExecuteSelected());
+        assert!(LAUNCHED.lock().unwrap().is_empty());
+        assert_eq!(unit.status(), Some(BUSY_MESSAGE));
+        // Choices made while busy are ignored
+        let ignored = unit.update(ElbeyMessage::ExecuteSelected());
+        assert!(iced_runtime::task::into_stream(ignored).is_none());
+
+        finish(&mut unit, task);
+        assert_eq!(*LAUNCHED.lock().unwrap(), vec!["test_app_id_1"]);
+        assert_eq!(unit.status(), None);
+    }
+
+    #[test]
+    fn test_activation_replaces_listing_or_shows_error() {
+        let script = r#"sh -c 'if [ "$1" = top ]; then printf "\0prompt\037sub\nsub\n"; else echo fail >&2; exit 1; fi' menu"#;
+        let provider = ScriptProvider::new(script, Duration::from_secs(5)).unwrap();
+        let (mut unit, _) = Elbey::new(test_flags(vec![vec![Arc::new(provider)]]));
+        let _ = unit.update(ElbeyMessage::ItemsLoaded(
+            0,
+            vec![Item::new("0", "top"), Item::new("1", "other")].into(),
+        ));
+
+        settle(&mut unit, ElbeyMessage::ExecuteSelected());
+        assert_eq!(unit.item(0).unwrap().title, "sub");
+        assert_eq!(unit.state.items.len(), 1);
+        assert_eq!(unit.hint(), "sub");
+
+        settle(&mut unit, ElbeyMessage::ExecuteSelected());
+        assert!(unit.state.message.as_deref().unwrap().contains("fail"));
+    }
+
+    #[test]
+    fn test_query_items_are_listed_first() {
+        let calc: Arc<dyn Provider> = Arc::new(CalcProvider::new(Output::Stdout));
+        let (mut unit, _) = Elbey::new(test_flags(vec![vec![apps_provider(|_| Ok(())), calc]]));
+        let _ = unit.update(ElbeyMessage::ItemsLoaded(0, test_items()));
+        let _ = unit.update(ElbeyMessage::ItemsLoaded(1, Listing::default()));
+
+        let _ = unit.update(ElbeyMessage::EntryUpdate("2 * (1 + 2)".to_string()));
+        assert_eq!(unit.selected_entry().unwrap().0, 1);
+        assert_eq!(unit.selected_entry().unwrap().1.title, "6");
+
+        let _ = unit.update(ElbeyMessage::EntryUpdate("t2".to_string()));
+        assert!(unit.state.query_items.is_empty());
+        assert_eq!(unit.selected_entry().unwrap().1.id, "test_app_id_2");
+    }
+
+    #[test]
+    fn test_fallback_row_and_history_recall() {
+        let shell: Arc<dyn Provider> = Arc::new(ShellProvider::new(
+            Terminal::new(Some("foot")).unwrap(),
+            |args| anyhow::bail!("ran {}", args.join(" ")),
+            |_| vec!["make".to_string(), "ls".to_string()],
+            |_, _| {},
+        ));
+        let mut flags = test_flags(vec![vec![apps_provider(|_| Ok(()))]]);
+        flags.fallbacks = vec![shell];
+        let (mut unit, _) = Elbey::new(flags);
+        let _ = unit.update(ElbeyMessage::ItemsLoaded(0, test_items()));
+
+        let _ = unit.update(ElbeyMessage::EntryUpdate("t1".to_string()));
+        assert!(unit.state.fallback_items.is_empty());
+
+        let _ = unit.update(ElbeyMessage::EntryUpdate("echo hi".to_string()));
+        assert_eq!(unit.row_count(), 1);
+        assert_eq!(unit.selected_entry().unwrap().1.title, "Run: echo hi");
+        settle(&mut unit, ElbeyMessage::ExecuteAlternate);
+        let message = unit.state.message.clone().unwrap();
+        assert!(message.starts_with("ran foot ") && message.ends_with(" -c echo hi"));
+
+        let _ = unit.update(ElbeyMessage::EntryUpdate(String::new()));
+        let mut recalled = Vec::new();
+        for key in [
+            Named::ArrowUp,
+            Named::ArrowUp,
+            Named::ArrowUp,
+            Named::ArrowDown,
+        ] {
+            let _ = unit.update(ElbeyMessage::KeyEvent(Key::Named(key)));
+            recalled.push(unit.state.entry.clone());
+        }
+        assert_eq!(recalled, vec!["make", "ls", "ls", "make"]);
+        let _ = unit.update(ElbeyMessage::KeyEvent(Key::Named(Named::ArrowDown)));
+        assert_eq!(unit.state.entry, "");
+        assert_eq!(unit.state.history_position, None);
+    }
+
+    #[test]
+    fn test_fallbacks_are_listed_in_configured_order() {
+        let web = WebProvider::new(bangs(&[]).unwrap(), |_| Ok(()))
+            .with_engine("g")
+            .unwrap();
+        let mut flags = test_flags(vec![vec![apps_provider(|_| Ok(()))]]);
+        flags.fallbacks = vec![
+            Arc::new(web),
+            Arc::new(CalcProvider::new(Output::Stdout)),
+            Arc::new(FilesProvider::new(|_| Ok(()))),
+        ];
+        let (mut unit, _) = Elbey::new(flags);
+        let _ = unit.update(ElbeyMessage::ItemsLoaded(0, test_items()));
+
+        let _ = unit.update(ElbeyMessage::EntryUpdate("holiday photos".to_string()));
+        let titles: Vec<&str> = (0..unit.row_count())
+            .map(|row| unit.row(row).unwrap().1.title.as_str())
+            .collect();
+        assert_eq!(
+            titles,
+            vec![
+                "Search Google: holiday photos",
+                "Search files: holiday photos"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_typed_path_lists_only_entries_and_tab_completes() {
+        let dir = std::env::temp_dir().join(format!("elbey-app-path-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("t1-notes")).unwrap();
+        let mut flags = test_flags(vec![vec![apps_provider(|_| Ok(()))]]);
+        flags.exclusive = vec![Arc::new(PathProvider::new(|_| Ok(())))];
+        let (mut unit, _) = Elbey::new(flags);
+        let _ = unit.update(ElbeyMessage::ItemsLoaded(0, test_items()));
+
+        let _ = unit.update(ElbeyMessage::EntryUpdate(format!("{}/t1", dir.display())));
+        assert_eq!(unit.row_count(), 1);
+        assert_eq!(unit.row(0).unwrap().1.title, "t1-notes/");
+
+        let _ = unit.update(ElbeyMessage::KeyEvent(Key::Named(Named::Tab)));
+        assert_eq!(unit.state.entry, format!("{}/t1-notes/", dir.display()));
+
+        let _ = unit.update(ElbeyMessage::EntryUpdate("t1".to_string()));
+        assert_eq!(unit.row(0).unwrap().1.title, "t1");
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_items_of_inactive_tab_are_not_listed() {
+        let run: Arc<dyn Provider> = Arc::new(RunProvider::new(EMPTY_LOADER, |_| Ok(())));
+        let (mut unit, _) =
+            Elbey::new(test_flags(vec![vec![apps_provider(|_| Ok(()))], vec![run]]));
+
+        let _ = unit.update(ElbeyMessage::SwitchTab);
+        assert_eq!(unit.hint(), "run");
+        let _ = unit.update(ElbeyMessage::ItemsLoaded(0, test_items()));
+        assert!(unit.state.items.is_empty());
+
+        let _ = unit.update(ElbeyMessage::SwitchTab);
+        assert_eq!(unit.hint(), "drun");
+        assert_eq!(unit.state.items.len(), 3);
+    }
+
+    #[test]
+    fn test_clicking_a_tab_selects_it() {
+        let tab = |name| -> Vec<Arc<dyn Provider>> {
+            vec![Arc::new(
+                ScriptProvider::new(name, Duration::from_secs(5)).unwrap(),
+            )]
+        };
+        let (mut unit, _) = Elbey::new(test_flags(vec![tab("a"), tab("b"), tab("c")]));
+
+        let _ = unit.update(ElbeyMessage::SelectTab(2));
+        assert_eq!(unit.state.tab, 2);
+        let _ = unit.update(ElbeyMessage::SelectTab(0));
+        assert_eq!(unit.state.tab, 0);
+        let _ = unit.update(ElbeyMessage::SelectTab(3));
+        assert_eq!(unit.state.tab, 0);
+    }
+
+    #[test]
+    fn test_combined_tab_lists_every_provider() {
+        let run_launcher: fn(&AppDescriptor) -> anyhow::Result<()> = |e| {
+            assert_eq!(e.appid, "test_app_id_1");
+            Ok(())
+        };
+        let run: Arc<dyn Provider> = Arc::new(RunProvider::new(TEST_ENTRY_LOADER, run_launcher));
+        let (mut unit, _) = Elbey::new(test_flags(vec![vec![
+            apps_provider(|_| unreachable!("the run item is selected")),
+            run,
+        ]]));
+
+        let _ = unit.update(ElbeyMessage::ItemsLoaded(0, test_items()));
+        let _ = unit.update(ElbeyMessage::ItemsLoaded(1, test_items()));
+        assert_eq!(unit.hint(), "drun+run");
+        assert_eq!(unit.state.items.len(), 6);
+
+        let _ = unit.update(ElbeyMessage::EntryUpdate("t1".to_string()));
+        assert_eq!(unit.state.filtered_indices.len(), 2);
+        let _ = unit.update(ElbeyMessage::KeyEvent(Key::Named(Named::ArrowDown)));
+        settle(&mut unit, ElbeyMessage::ExecuteSelected());
+    }
 }
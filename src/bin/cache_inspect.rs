@@ -2,9 +2,9 @@ use std::env;
 use std::process::exit;
 use std::time::Instant;
 
-use elbey_cache::{AppDescriptor, Cache};
+use elbey_cache::{desktop_entries, AppDescriptor, Cache};
 use freedesktop_desktop_entry::{
-    current_desktop, default_paths, get_languages_from_env, DesktopEntry, Iter,
+    current_desktop, default_paths, get_languages_from_env, DesktopEntry,
 };
 
 fn main() -> anyhow::Result<()> {
@@ -58,8 +58,8 @@ fn parse_count() -> usize {
 fn find_all_apps() -> Vec<AppDescriptor> {
     let locales = get_languages_from_env();
 
-    let app_list_iter = Iter::new(default_paths())
-        .entries(Some(&locales))
+    let app_list_iter = desktop_entries(default_paths(), &locales, |_| false)
+        .into_iter()
         .filter(|entry| !entry.no_display())
         .filter(|entry| entry.desktop_entry("Name").is_some()) // Ignore apps w/out titles
         .filter(|entry| entry.exec().is_some());
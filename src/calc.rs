@@ -0,0 +1,423 @@
+//! Evaluation of arithmetic typed into the query.
+//!
+//! Supports `+ - * / ^` (also `**`, `×`, `÷`), `mod`/`%` as modulo between operands, `%` after a
+//! value as a percentage (`200 + 10%` adds ten percent of 200), `!` factorial, parentheses,
+//! hex/octal/binary literals (`0xff`, `0o17`, `0b1010`), the constants `pi`, `tau` and `e`, and
+//! common functions such as `sqrt`, `sin` or `log`.
+use std::f64::consts::{E, PI, TAU};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Op(char),
+    LParen,
+    RParen,
+    Comma,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Number(f64),
+    Percent(Box<Expr>),
+    Negate(Box<Expr>),
+    Factorial(Box<Expr>),
+    Binary(char, Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+}
+
+/// Split `input` into tokens, or `None` if it contains anything unexpected.
+fn tokenize(input: &str) -> Option<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let chars: Vec<char> = input.chars().collect();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            ' ' | '\t' => i += 1,
+            '0'..='9' | '.' => {
+                let radix = match (c, chars.get(i + 1)) {
+                    ('0', Some('x' | 'X')) => 16,
+                    ('0', Some('o' | 'O')) => 8,
+                    ('0', Some('b' | 'B')) => 2,
+                    _ => 10,
+                };
+                if radix != 10 {
+                    let start = i + 2;
+                    let mut end = start;
+                    while end < chars.len() && (chars[end].is_digit(radix) || chars[end] == '_') {
+                        end += 1;
+                    }
+                    let digits: String = chars[start..end].iter().filter(|c| **c != '_').collect();
+                    tokens.push(Token::Number(
+                        i64::from_str_radix(&digits, radix).ok()? as f64
+                    ));
+                    i = end;
+                    continue;
+                }
+
+                // Digits may be grouped with '_', e.g. 2_000
+                let separator = |i: usize| {
+                    chars[i] == '_'
+                        && i > 0
+                        && chars[i - 1].is_ascii_digit()
+                        && chars.get(i + 1).is_some_and(char::is_ascii_digit)
+                };
+                let start = i;
+                while i < chars.len()
+                    && (chars[i].is_ascii_digit() || chars[i] == '.' || separator(i))
+                {
+                    i += 1;
+                }
+                // Exponent, e.g. 1.5e3, but not the constant in 2e
+                if i < chars.len()
+                    && (chars[i] == 'e' || chars[i] == 'E')
+                    && chars
+                        .get(i + 1)
+                        .is_some_and(|next| next.is_ascii_digit() || *next == '-' || *next == '+')
+                {
+                    i += 2;
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+                let literal: String = chars[start..i].iter().filter(|c| **c != '_').collect();
+                tokens.push(Token::Number(literal.parse().ok()?));
+            }
+            'a'..='z' | 'A'..='Z' => {
+                let start = i;
+                while i < chars.len() && chars[i].is_ascii_alphanumeric() {
+                    i += 1;
+                }
+                tokens.push(Token::Ident(
+                    chars[start..i].iter().collect::<String>().to_lowercase(),
+                ));
+            }
+            '*' if chars.get(i + 1) == Some(&'*') => {
+                tokens.push(Token::Op('^'));
+                i += 2;
+            }
+            '+' | '-' | '*' | '/' | '^' | '%' | '!' => {
+                tokens.push(Token::Op(c));
+                i += 1;
+            }
+            '×' => {
+                tokens.push(Token::Op('*'));
+                i += 1;
+            }
+            '÷' => {
+                tokens.push(Token::Op('/'));
+                i += 1;
+            }
+            '(' => {
+                tokens.push(Token::LParen);
+                i += 1;
+            }
+            ')' => {
+                tokens.push(Token::RParen);
+                i += 1;
+            }
+            ',' => {
+                tokens.push(Token::Comma);
+                i += 1;
+            }
+            _ => return None,
+        }
+    }
+
+    Some(tokens)
+}
+
+/// A recursive descent parser over the tokens of an expression.
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    // expr := term (('+' | '-') term)*
+    fn expr(&mut self) -> Option<Expr> {
+        let mut left = self.term()?;
+        while let Some(Token::Op(op @ ('+' | '-'))) = self.peek().cloned() {
+            self.position += 1;
+            let right = self.term()?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+        Some(left)
+    }
+
+    // term := unary (('*' | '/' | '%' | 'mod') unary)*
+    fn term(&mut self) -> Option<Expr> {
+        let mut left = self.unary()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Op(op @ ('*' | '/' | '%'))) => *op,
+                Some(Token::Ident(ident)) if ident == "mod" => '%',
+                _ => break,
+            };
+            self.position += 1;
+            let right = self.unary()?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+        Some(left)
+    }
+
+    // unary := ('-' | '+') unary | power
+    fn unary(&mut self) -> Option<Expr> {
+        if self.eat(&Token::Op('-')) {
+            return Some(Expr::Negate(Box::new(self.unary()?)));
+        }
+        if self.eat(&Token::Op('+')) {
+            return self.unary();
+        }
+        self.power()
+    }
+
+    // power := postfix ('^' unary)?, right associative
+    fn power(&mut self) -> Option<Expr> {
+        let base = self.postfix()?;
+        if self.eat(&Token::Op('^')) {
+            let exponent = self.unary()?;
+            return Some(Expr::Binary('^', Box::new(base), Box::new(exponent)));
+        }
+        Some(base)
+    }
+
+    // postfix := primary ('!' | '%')*, where '%' is a percentage unless an operand follows
+    fn postfix(&mut self) -> Option<Expr> {
+        let mut value = self.primary()?;
+        loop {
+            if self.eat(&Token::Op('!')) {
+                value = Expr::Factorial(Box::new(value));
+            } else if self.peek() == Some(&Token::Op('%')) && !self.operand_follows(1) {
+                self.position += 1;
+                value = Expr::Percent(Box::new(value));
+            } else {
+                return Some(value);
+            }
+        }
+    }
+
+    fn operand_follows(&self, offset: usize) -> bool {
+        matches!(
+            self.tokens.get(self.position + offset),
+            Some(Token::Number(_) | Token::Ident(_) | Token::LParen)
+        )
+    }
+
+    // primary := number | constant | function '(' args ')' | '(' expr ')'
+    fn primary(&mut self) -> Option<Expr> {
+        match self.next()? {
+            Token::Number(value) => Some(Expr::Number(value)),
+            Token::LParen => {
+                let inner = self.expr()?;
+                self.eat(&Token::RParen).then_some(inner)
+            }
+            Token::Ident(name) => {
+                if !self.eat(&Token::LParen) {
+                    return constant(&name).map(Expr::Number);
+                }
+                let mut args = vec![self.expr()?];
+                while self.eat(&Token::Comma) {
+                    args.push(self.expr()?);
+                }
+                self.eat(&Token::RParen).then_some(Expr::Call(name, args))
+            }
+            _ => None,
+        }
+    }
+}
+
+fn constant(name: &str) -> Option<f64> {
+    match name {
+        "pi" => Some(PI),
+        "tau" => Some(TAU),
+        "e" => Some(E),
+        _ => None,
+    }
+}
+
+fn call(name: &str, args: &[f64]) -> Option<f64> {
+    let value = match (name, args) {
+        ("sqrt", [x]) => x.sqrt(),
+        ("cbrt", [x]) => x.cbrt(),
+        ("abs", [x]) => x.abs(),
+        ("sin", [x]) => x.sin(),
+        ("cos", [x]) => x.cos(),
+        ("tan", [x]) => x.tan(),
+        ("asin", [x]) => x.asin(),
+        ("acos", [x]) => x.acos(),
+        ("atan", [x]) => x.atan(),
+        ("ln", [x]) => x.ln(),
+        ("log", [x]) => x.log10(),
+        ("log", [x, base]) => x.log(*base),
+        ("log2", [x]) => x.log2(),
+        ("exp", [x]) => x.exp(),
+        ("floor", [x]) => x.floor(),
+        ("ceil", [x]) => x.ceil(),
+        ("round", [x]) => x.round(),
+        ("min", [first, rest @ ..]) => rest.iter().fold(*first, |a, b| a.min(*b)),
+        ("max", [first, rest @ ..]) => rest.iter().fold(*first, |a, b| a.max(*b)),
+        ("pow", [x, y]) => x.powf(*y),
+        _ => return None,
+    };
+    Some(value)
+}
+
+fn eval(expr: &Expr) -> Option<f64> {
+    let value = match expr {
+        Expr::Number(value) => *value,
+        Expr::Percent(value) => eval(value)? / 100.0,
+        Expr::Negate(value) => -eval(value)?,
+        Expr::Factorial(value) => {
+            let n = eval(value)?;
+            if n < 0.0 || n.fract() != 0.0 || n > 170.0 {
+                return None;
+            }
+            (1..=n as u64).map(|i| i as f64).product()
+        }
+        // Adding or subtracting a percentage scales the left operand, as on a desk calculator
+        Expr::Binary(op @ ('+' | '-'), left, right) if matches!(**right, Expr::Percent(_)) => {
+            let left = eval(left)?;
+            let change = left * eval(right)?;
+            if *op == '+' {
+                left + change
+            } else {
+                left - change
+            }
+        }
+        Expr::Binary(op, left, right) => {
+            let (left, right) = (eval(left)?, eval(right)?);
+            match op {
+                '+' => left + right,
+                '-' => left - right,
+                '*' => left * right,
+                '/' => left / right,
+                '%' => left % right,
+                '^' => left.powf(right),
+                _ => return None,
+            }
+        }
+        Expr::Call(name, args) => {
+            let args = args.iter().map(eval).collect::<Option<Vec<_>>>()?;
+            call(name, &args)?
+        }
+    };
+    value.is_finite().then_some(value)
+}
+
+/// Evaluate `query` if it is an arithmetic expression.
+///
+/// Returns `None` for anything else, including a plain decimal number or constant on its own,
+/// so that queries such as `2048 game` or `e` are left to match items.
+pub fn evaluate(query: &str) -> Option<f64> {
+    let tokens = tokenize(query.trim())?;
+    let mut parser = Parser {
+        tokens,
+        position: 0,
+    };
+    let expr = parser.expr()?;
+    if parser.position != parser.tokens.len() {
+        return None;
+    }
+
+    let is_calculation = match &expr {
+        Expr::Number(_) => {
+            let literal = query.trim().to_lowercase();
+            ["0x", "0o", "0b"]
+                .iter()
+                .any(|prefix| literal.starts_with(prefix))
+        }
+        _ => true,
+    };
+    if !is_calculation {
+        return None;
+    }
+    eval(&expr)
+}
+
+/// Format a result without float noise: integers in full, other values to 12 significant digits.
+pub fn format_number(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        return format!("{}", value as i64);
+    }
+    let magnitude = value.abs().log10().floor() as i32;
+    if !(-6..15).contains(&magnitude) {
+        return format!("{value:e}");
+    }
+    let decimals = (11 - magnitude).max(0) as usize;
+    let formatted = format!("{value:.decimals$}");
+    formatted
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn calc(query: &str) -> Option<String> {
+        evaluate(query).map(format_number)
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        assert_eq!(calc("1 + 2 * 3").as_deref(), Some("7"));
+        assert_eq!(calc("(1 + 2) * 3").as_deref(), Some("9"));
+        assert_eq!(calc("2 ^ 3 ^ 2").as_deref(), Some("512"));
+        assert_eq!(calc("-2 ** 2").as_deref(), Some("-4"));
+        assert_eq!(calc("10 / 4").as_deref(), Some("2.5"));
+        assert_eq!(calc("10 mod 3").as_deref(), Some("1"));
+        assert_eq!(calc("10 % 3").as_deref(), Some("1"));
+        assert_eq!(calc("0.1 + 0.2").as_deref(), Some("0.3"));
+        assert_eq!(calc("5!").as_deref(), Some("120"));
+    }
+
+    #[test]
+    fn test_functions_literals_and_percentages() {
+        assert_eq!(calc("sqrt(16) + abs(-2)").as_deref(), Some("6"));
+        assert_eq!(calc("max(1, 7, 3)").as_deref(), Some("7"));
+        assert_eq!(calc("round(2 * pi)").as_deref(), Some("6"));
+        assert_eq!(calc("0xff + 0b1").as_deref(), Some("256"));
+        assert_eq!(calc("0x10").as_deref(), Some("16"));
+        assert_eq!(calc("2_000 * 3").as_deref(), Some("6000"));
+        assert_eq!(calc("0b1111_0000").as_deref(), Some("240"));
+        assert_eq!(calc("50%").as_deref(), Some("0.5"));
+        assert_eq!(calc("200 + 10%").as_deref(), Some("220"));
+        assert_eq!(calc("80 - 25%").as_deref(), Some("60"));
+        assert_eq!(calc("20% * 50").as_deref(), Some("10"));
+    }
+
+    #[test]
+    fn test_queries_that_are_not_calculations() {
+        assert_eq!(calc("2048 game"), None);
+        assert_eq!(calc("2048"), None);
+        assert_eq!(calc("e"), None);
+        assert_eq!(calc("firefox"), None);
+        assert_eq!(calc("1 +"), None);
+        assert_eq!(calc("2 _ 3"), None);
+        assert_eq!(calc("1 / 0"), None);
+        assert_eq!(calc("unknown(2)"), None);
+    }
+}
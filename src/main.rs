@@ -1,31 +1,75 @@
 //! Elbey - a desktop app launcher
 #![doc(html_logo_url = "https://github.com/kgilmer/elbey/blob/main/elbey.svg")]
 mod app;
+mod calc;
+mod mime_apps;
+mod mime_types;
+mod output;
+mod overrides;
+mod provider;
+mod providers;
+mod search_paths;
+mod terminal;
+mod units;
 mod values;
 
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::process::exit;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
 
 use crate::values::*;
 use anyhow::Context;
 use app::{Elbey, ElbeyFlags};
 use argh::FromArgs;
-use elbey_cache::{clear_cache_dir, AppDescriptor, Cache};
-use freedesktop_desktop_entry::{
-    current_desktop, default_paths, get_languages_from_env, DesktopEntry, Iter,
+use elbey_cache::{
+    clear_cache_dir, desktop_entries, desktop_entries_for_ids, desktop_file_id, man_dirs,
+    path_dirs, watch_desktop_dirs, AppDescriptor, Cache, ManPage, WatchEvent,
+    DEFAULT_WATCH_DEBOUNCE,
 };
+use freedesktop_desktop_entry::{current_desktop, get_languages_from_env, DesktopEntry};
+use iced::futures::stream::BoxStream;
+use iced::futures::{SinkExt, StreamExt};
 use iced::theme::{Custom, Palette};
 use iced::{Color, Font, Pixels, Theme};
 use iced_layershell::application;
 use iced_layershell::reexport::{Anchor, KeyboardInteractivity, Layer};
 use iced_layershell::settings::{LayerShellSettings, Settings, StartMode};
 use lazy_static::lazy_static;
+use mime_apps::{Associations, Target};
+use mime_types::MimeDatabase;
+use output::Output;
+use provider::Provider;
+use providers::apps::{AppsProvider, AppsUpdate};
+use providers::calc::CalcProvider;
+use providers::dmenu::{DmenuProvider, Format};
+use providers::emoji::EmojiProvider;
+use providers::files::FilesProvider;
+use providers::hidden::{HiddenApp, HiddenProvider};
+use providers::man::ManProvider;
+use providers::open_with::OpenWithProvider;
+use providers::pass::{self, PassProvider, DEFAULT_PASS_COMMAND, DEFAULT_PASS_OTP_COMMAND};
+use providers::path::PathProvider;
+use providers::processes::{self, ProcessesProvider};
+use providers::recent::{self, RecentProvider};
+use providers::run::RunProvider;
+use providers::script::ScriptProvider;
+use providers::session::{session_actions, SessionAction, SessionProvider};
+use providers::shell::ShellProvider;
+use providers::ssh::{self, SshProvider};
+use providers::web::{bangs, Bang, WebProvider};
+use search_paths::SearchPaths;
+use terminal::Terminal;
 
 lazy_static! {
     pub(crate) static ref CACHE: Arc<Mutex<Cache>> =
         Arc::new(Mutex::new(Cache::new(find_all_apps)));
 }
 
+/// Directories scanned for desktop entries, as configured on the command line.
+static SEARCH_PATHS: OnceLock<SearchPaths> = OnceLock::new();
+
 #[derive(FromArgs)]
 /// Desktop app launcher
 struct EbleyArgs {
@@ -57,10 +101,94 @@ struct EbleyArgs {
     #[argh(option)]
     hint: Option<String>,
 
+    /// modes to show, separated by ',' into tabs and combined in one tab with '+', e.g. drun+calc,run (default: drun): drun, run, calc, emoji, man, pass, processes, recent, session, ssh or web; see the README for what each lists
+    #[argh(option)]
+    mode: Option<String>,
+
+    /// list newline-separated items read from stdin and print the chosen one to stdout; exits 0 when an item is selected, 1 when cancelled and 2 when text matching no item is entered
+    #[argh(switch)]
+    dmenu: bool,
+
+    /// read JSON lines with title, subtitle, icon, keywords and id fields from stdin and print the chosen object; implies --dmenu
+    #[argh(switch)]
+    json: bool,
+
+    /// match items ignoring case in dmenu mode
+    #[argh(switch, short = 'i')]
+    case_insensitive: bool,
+
+    /// print the zero-based index of the chosen item instead of its text in dmenu mode
+    #[argh(switch)]
+    index: bool,
+
+    /// allow marking several items with Shift+Enter in dmenu mode, printing each one
+    #[argh(switch)]
+    multi_select: bool,
+
+    /// run a command that lists entries, and re-run it with the chosen entry to show a new list or close, like rofi's script mode
+    #[argh(option)]
+    script: Option<String>,
+
+    /// seconds to wait for each run of the --script command (default 10)
+    #[argh(option)]
+    script_timeout: Option<u64>,
+
+    /// list the apps that can open a file or URL, defaults from mimeapps.list first, and open it with the chosen one
+    #[argh(option)]
+    open_with: Option<String>,
+
+    /// list the apps hidden with Ctrl+H, and show the chosen one again
+    #[argh(switch)]
+    show_hidden: bool,
+
+    /// how Ctrl+H hides a drun app: blocklist (remember it in elbey's cache, the default) or override (write a copy of its desktop entry with NoDisplay=true to ~/.local/share/applications, hiding it from other menus too)
+    #[argh(option)]
+    hide_with: Option<String>,
+
+    /// command run for the chosen pass mode entry, with %s for the entry name (default: pass show -c %s)
+    #[argh(option)]
+    pass_command: Option<String>,
+
+    /// command run for a pass mode entry chosen with Ctrl+Enter, e.g. to copy a one-time password (default: pass otp -c %s)
+    #[argh(option)]
+    pass_otp_command: Option<String>,
+
+    /// actions offered, in order, when a query matches nothing: run (run it as a shell command), web or web:<bang> (search the web, with ddg unless another bang is named), calc (calculate), files (search file names with locate) and path (open it as a path); separated by ',' (default: run)
+    #[argh(option)]
+    fallback: Option<String>,
+
+    /// command that receives computed results and picked characters instead of stdout, e.g. wl-copy or wtype -; a %s argument is replaced by the text, otherwise the text is written to its stdin
+    #[argh(option)]
+    copy_command: Option<String>,
+
+    /// command that runs a program in a terminal, followed by the program and its arguments, used by ssh mode and by Ctrl+Enter on the "Run:" row offered when nothing matches (default: $TERMINAL -e, or xterm -e)
+    #[argh(option)]
+    terminal: Option<String>,
+
+    /// replace the command of a session mode action, as <action>=<command>, e.g. lock="swaylock -f" (repeatable)
+    #[argh(option)]
+    session_command: Vec<String>,
+
+    /// ask for confirmation in session mode before logging out, rebooting or shutting down
+    #[argh(switch)]
+    confirm_session: bool,
+
+    /// add or replace a web mode search shortcut, as <prefix>=<url template> with {} for the search terms, e.g. w=https://en.wikipedia.org/w/index.php?search={} (repeatable; built in: g, ddg, gh, crates)
+    #[argh(option)]
+    bang: Vec<String>,
+
     /// print the desktop application search paths and exit
     #[argh(switch)]
     list_search_paths: bool,
 
+    /// additional directory to scan for desktop entries (repeatable)
+    #[argh(option)]
+    extra_app_dir: Vec<String>,
+
+    /// directory or glob pattern to exclude from the desktop entry scan (repeatable)
+    #[argh(option)]
+    exclude_app_dir: Vec<String>,
+
     /// clear the application cache before loading apps
     #[argh(switch)]
     reset_cache: bool,
@@ -103,6 +231,244 @@ fn parse_theme(name: &str) -> Option<Theme> {
     }
 }
 
+/// Settings shared by the modes, parsed from the command line.
+struct ModeOptions {
+    output: Output,
+    terminal: Terminal,
+    session_actions: Vec<SessionAction>,
+    confirm_session: bool,
+    bangs: Vec<Bang>,
+    pass_command: String,
+    pass_otp_command: String,
+    /// How drun mode hides apps
+    hider: fn(&AppDescriptor) -> anyhow::Result<()>,
+}
+
+impl ModeOptions {
+    fn new(args: &EbleyArgs) -> Self {
+        let output = Output::new(args.copy_command.as_deref()).unwrap_or_else(|err| {
+            eprintln!("Invalid copy command: {err}");
+            exit(1);
+        });
+        let terminal = Terminal::new(args.terminal.as_deref()).unwrap_or_else(|err| {
+            eprintln!("Invalid terminal command: {err}");
+            exit(1);
+        });
+        let session_actions = session_actions(&args.session_command).unwrap_or_else(|err| {
+            eprintln!("Invalid session command: {err}");
+            exit(1);
+        });
+        let bangs = bangs(&args.bang).unwrap_or_else(|err| {
+            eprintln!("Invalid bang: {err}");
+            exit(1);
+        });
+        let hider = match args.hide_with.as_deref() {
+            None | Some("blocklist") => hide_in_blocklist,
+            Some("override") => hide_with_override,
+            Some(other) => {
+                eprintln!("Invalid --hide-with {other}: expected blocklist or override");
+                exit(1);
+            }
+        };
+        ModeOptions {
+            output,
+            terminal,
+            session_actions,
+            confirm_session: args.confirm_session,
+            bangs,
+            pass_command: args
+                .pass_command
+                .clone()
+                .unwrap_or_else(|| DEFAULT_PASS_COMMAND.to_string()),
+            pass_otp_command: args
+                .pass_otp_command
+                .clone()
+                .unwrap_or_else(|| DEFAULT_PASS_OTP_COMMAND.to_string()),
+            hider,
+        }
+    }
+}
+
+/// Create the provider for a mode named on the command line.
+fn provider(name: &str, options: &ModeOptions) -> Option<Arc<dyn Provider>> {
+    let output = &options.output;
+    match name {
+        "drun" => Some(Arc::new(
+            AppsProvider::new(load_apps, launch_app, Some(watch_apps))
+                .with_pins(pins, set_pins)
+                .with_hider(options.hider),
+        )),
+        "run" => Some(Arc::new(RunProvider::new(load_executables, run_command))),
+        "calc" => Some(Arc::new(CalcProvider::new(output.clone()))),
+        "emoji" => Some(Arc::new(EmojiProvider::new(
+            output.clone(),
+            usage_counts,
+            record_usage,
+        ))),
+        "man" => Some(Arc::new(ManProvider::new(
+            load_man_pages,
+            options.terminal.clone(),
+            spawn_command,
+        ))),
+        "pass" => {
+            let provider = PassProvider::new(
+                pass::default_store_dir().unwrap_or_default(),
+                &options.pass_command,
+                &options.pass_otp_command,
+                spawn_command,
+            );
+            match provider {
+                Ok(provider) => Some(Arc::new(provider)),
+                Err(err) => {
+                    eprintln!("Invalid pass command: {err}");
+                    exit(1);
+                }
+            }
+        }
+        "processes" => {
+            let uid = processes::current_uid().unwrap_or_else(|err| {
+                eprintln!("Failed to read the user id: {err}");
+                exit(1);
+            });
+            Some(Arc::new(ProcessesProvider::new(
+                PathBuf::from("/proc"),
+                uid,
+                load_apps,
+                processes::send_signal,
+            )))
+        }
+        "recent" => Some(Arc::new(RecentProvider::new(
+            recent::default_path().unwrap_or_default(),
+            spawn_command,
+            open_target,
+        ))),
+        "session" => Some(Arc::new(SessionProvider::new(
+            options.session_actions.clone(),
+            options.confirm_session,
+            spawn_command,
+        ))),
+        "ssh" => Some(Arc::new(SshProvider::new(
+            ssh::default_dir().unwrap_or_default(),
+            options.terminal.clone(),
+            spawn_command,
+            usage_counts,
+            record_usage,
+        ))),
+        "web" => Some(Arc::new(WebProvider::new(
+            options.bangs.clone(),
+            open_target,
+        ))),
+        _ => None,
+    }
+}
+
+/// Parse `--mode` into tabs of providers: tabs are separated by ',' and the modes listed
+/// together in a tab by '+'.
+fn parse_tabs(args: &EbleyArgs, options: &ModeOptions) -> Vec<Vec<Arc<dyn Provider>>> {
+    let modes = args.mode.as_deref().unwrap_or(DEFAULT_MODE);
+    modes
+        .split(',')
+        .map(|tab| {
+            tab.split('+')
+                .map(|name| {
+                    provider(name.trim(), options).unwrap_or_else(|| {
+                        eprintln!("unknown mode: {name}");
+                        exit(1);
+                    })
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Create a fallback named on the command line: `run`, `web` or `web:<bang>`, `calc`, `files`
+/// or `path`.
+fn fallback(name: &str, options: &ModeOptions) -> anyhow::Result<Arc<dyn Provider>> {
+    let (name, argument) = name.split_once(':').unwrap_or((name, ""));
+    Ok(match name {
+        "run" => Arc::new(ShellProvider::new(
+            options.terminal.clone(),
+            spawn_command,
+            command_history,
+            record_history,
+        )),
+        "web" => {
+            let engine = if argument.is_empty() { "ddg" } else { argument };
+            Arc::new(WebProvider::new(options.bangs.clone(), open_target).with_engine(engine)?)
+        }
+        "calc" => Arc::new(CalcProvider::new(options.output.clone())),
+        "files" => Arc::new(FilesProvider::new(open_target)),
+        "path" => Arc::new(PathProvider::new(open_target)),
+        _ => anyhow::bail!("unknown fallback: {name}"),
+    })
+}
+
+/// Parse `--fallback` into the providers offered, in order, when a query matches nothing.
+fn fallbacks(args: &EbleyArgs, options: &ModeOptions) -> Vec<Arc<dyn Provider>> {
+    let names = args.fallback.as_deref().unwrap_or(DEFAULT_FALLBACKS);
+    names
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(|name| {
+            fallback(name, options).unwrap_or_else(|err| {
+                eprintln!("Invalid fallback: {err}");
+                exit(1);
+            })
+        })
+        .collect()
+}
+
+/// Create the dmenu provider from the lines on stdin.
+fn dmenu_provider(args: &EbleyArgs) -> Arc<dyn Provider> {
+    let lines = std::io::stdin()
+        .lines()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_or_else(|err| {
+            eprintln!("Failed to read stdin: {err}");
+            exit(1);
+        });
+    let format = if args.json {
+        Format::Json
+    } else {
+        Format::Text
+    };
+    Arc::new(DmenuProvider::new(lines, format, args.index))
+}
+
+/// Create the provider for `--open-with`.
+fn open_with_provider(target: &str) -> Arc<dyn Provider> {
+    let database = MimeDatabase::load();
+    match Target::new(target, &database) {
+        Ok(target) => Arc::new(OpenWithProvider::new(
+            target,
+            database,
+            installed_entries,
+            Associations::load(),
+            spawn_command,
+        )),
+        Err(err) => {
+            eprintln!("Cannot open {target}: {err:#}");
+            exit(1);
+        }
+    }
+}
+
+/// Create the provider for `--script`.
+fn script_provider(script: &str, args: &EbleyArgs) -> Arc<dyn Provider> {
+    let timeout = args
+        .script_timeout
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_SCRIPT_TIMEOUT);
+    match ScriptProvider::new(script, timeout) {
+        Ok(provider) => Arc::new(provider),
+        Err(err) => {
+            eprintln!("Invalid script command: {err}");
+            exit(1);
+        }
+    }
+}
+
 fn parse_hint(args: &EbleyArgs) -> String {
     if let Some(h) = &args.hint {
         if h.len() > 16 {
@@ -119,13 +485,21 @@ fn parse_hint(args: &EbleyArgs) -> String {
 fn main() -> Result<(), iced_layershell::Error> {
     let args: EbleyArgs = argh::from_env();
 
-    if args.list_search_paths {
-        for path in default_paths() {
-            println!("{}", path.display());
+    let search_paths = match SearchPaths::new(&args.extra_app_dir, &args.exclude_app_dir) {
+        Ok(paths) => paths,
+        Err(err) => {
+            eprintln!("{err}");
+            exit(1);
         }
+    };
+
+    if args.list_search_paths {
+        print_search_paths(&search_paths);
         return Ok(());
     }
 
+    let _ = SEARCH_PATHS.set(search_paths);
+
     if args.reset_cache {
         if let Err(err) = clear_cache_dir() {
             eprintln!("Failed to delete cache: {err}");
@@ -139,14 +513,46 @@ fn main() -> Result<(), iced_layershell::Error> {
         .and_then(parse_theme)
         .unwrap_or(DEFAULT_THEME);
 
+    let dmenu = args.dmenu || args.json;
+    // Fallbacks and typed paths are only offered in launcher modes, where a query is not a
+    // choice of its own
+    let (tabs, fallbacks, exclusive) = if dmenu {
+        (vec![vec![dmenu_provider(&args)]], Vec::new(), Vec::new())
+    } else if let Some(target) = &args.open_with {
+        (
+            vec![vec![open_with_provider(target)]],
+            Vec::new(),
+            Vec::new(),
+        )
+    } else if args.show_hidden {
+        let hidden: Arc<dyn Provider> = Arc::new(HiddenProvider::new(hidden_apps, unhide_app));
+        (vec![vec![hidden]], Vec::new(), Vec::new())
+    } else if let Some(script) = &args.script {
+        (
+            vec![vec![script_provider(script, &args)]],
+            Vec::new(),
+            Vec::new(),
+        )
+    } else {
+        let options = ModeOptions::new(&args);
+        let paths: Arc<dyn Provider> = Arc::new(PathProvider::new(open_target));
+        (
+            parse_tabs(&args, &options),
+            fallbacks(&args, &options),
+            vec![paths],
+        )
+    };
     let flags = ElbeyFlags {
-        apps_loader: load_apps,
-        app_launcher: launch_app,
+        tabs,
+        fallbacks,
+        exclusive,
         theme,
         icon_size: args.icon_size.unwrap_or(DEFAULT_ICON_SIZE),
         hint: parse_hint(&args),
         filter_font_size: args.filter_font_size.unwrap_or(DEFAULT_TEXT_SIZE),
         entries_font_size: args.entries_font_size.unwrap_or(DEFAULT_TEXT_SIZE),
+        case_sensitive: dmenu && !args.case_insensitive,
+        multi_select: dmenu && args.multi_select,
     };
 
     let iced_settings = Settings {
@@ -212,23 +618,354 @@ fn launch_app(entry: &AppDescriptor) -> anyhow::Result<()> {
     exit(0);
 }
 
+/// Run a command from run mode.  This implementation exits the process upon successful launch.
+fn run_command(entry: &AppDescriptor) -> anyhow::Result<()> {
+    let exec = entry.exec.as_deref().context("Missing command")?;
+    let args = shell_words::split(exec)?;
+    let (program, args) = args.split_first().context("Empty command")?;
+
+    std::process::Command::new(program)
+        .args(args)
+        .spawn()
+        .context("Failed to spawn command")
+        .map(|_| ())?;
+
+    if let Ok(cache) = CACHE.lock().as_mut() {
+        cache.record_run(&entry.appid)?;
+    } else {
+        eprint!("Failed to acquire cache");
+    }
+
+    exit(0);
+}
+
+/// Start a program with arguments.  This implementation exits the process upon successful launch.
+fn spawn_command(args: &[String]) -> anyhow::Result<()> {
+    let (program, args) = args.split_first().context("Empty command")?;
+    std::process::Command::new(program)
+        .args(args)
+        .spawn()
+        .with_context(|| format!("Failed to spawn {program}"))?;
+    exit(0);
+}
+
+/// Open a path or URL with its default application, as chosen in `mimeapps.list`.  This
+/// implementation exits the process upon successful launch.
+fn open_target(target: &str) -> anyhow::Result<()> {
+    let database = MimeDatabase::load();
+    let target = Target::new(target, &database)?;
+    let command = Associations::load().open_command(&target, &database, &installed_entries())?;
+    spawn_command(&command)
+}
+
+/// Usage counts of the items picked in `mode`.
+fn usage_counts(mode: &str) -> HashMap<String, usize> {
+    let cache = CACHE.lock().expect("Failed to acquire cache");
+    cache.usage_counts(mode).unwrap_or_else(|err| {
+        eprintln!("Failed to load {mode} usage: {err}");
+        HashMap::new()
+    })
+}
+
+/// Count a pick of `key` in `mode`.
+fn record_usage(mode: &str, key: &str) {
+    if let Ok(cache) = CACHE.lock().as_mut() {
+        if let Err(err) = cache.record_usage(mode, key) {
+            eprintln!("Failed to record {mode} usage: {err}");
+        }
+    } else {
+        eprint!("Failed to acquire cache");
+    }
+}
+
+/// The ids of the items pinned in `mode`, in order.
+fn pins(mode: &str) -> Vec<String> {
+    let cache = CACHE.lock().expect("Failed to acquire cache");
+    cache.pins(mode).unwrap_or_else(|err| {
+        eprintln!("Failed to load {mode} pins: {err}");
+        Vec::new()
+    })
+}
+
+/// Replace the items pinned in `mode`.
+fn set_pins(mode: &str, pins: &[String]) -> anyhow::Result<()> {
+    let mut cache = CACHE
+        .lock()
+        .map_err(|_| anyhow::anyhow!("Failed to acquire cache"))?;
+    cache.set_pins(mode, pins)
+}
+
+/// The text entered in `mode`, most recent first.
+fn command_history(mode: &str) -> Vec<String> {
+    let cache = CACHE.lock().expect("Failed to acquire cache");
+    cache.history(mode).unwrap_or_else(|err| {
+        eprintln!("Failed to load {mode} history: {err}");
+        Vec::new()
+    })
+}
+
+/// Add `entry` to the history of `mode`.
+fn record_history(mode: &str, entry: &str) {
+    if let Ok(cache) = CACHE.lock().as_mut() {
+        if let Err(err) = cache.record_history(mode, entry) {
+            eprintln!("Failed to record {mode} history: {err}");
+        }
+    } else {
+        eprint!("Failed to acquire cache");
+    }
+}
+
+fn load_executables() -> Vec<AppDescriptor> {
+    let mut cache = CACHE.lock().expect("Failed to acquire cache");
+    cache.load_executables(&path_dirs()).unwrap_or_else(|err| {
+        eprintln!("Failed to load executables: {err}");
+        Vec::new()
+    })
+}
+
+fn load_man_pages() -> Vec<ManPage> {
+    let mut cache = CACHE.lock().expect("Failed to acquire cache");
+    cache.load_man_pages(&man_dirs()).unwrap_or_else(|err| {
+        eprintln!("Failed to load man pages: {err}");
+        Vec::new()
+    })
+}
+
+/// Print each search path with its origin, marking those removed by an exclusion.
+fn print_search_paths(search_paths: &SearchPaths) {
+    for dir in search_paths.dirs() {
+        if search_paths.is_excluded(&dir.path) {
+            println!("{}\t{}\t(excluded)", dir.origin, dir.path.display());
+        } else {
+            println!("{}\t{}", dir.origin, dir.path.display());
+        }
+    }
+    for pattern in search_paths.excludes() {
+        println!("exclude\t{pattern}");
+    }
+}
+
 fn load_apps() -> Vec<AppDescriptor> {
     let mut cache = CACHE.lock().expect("Failed to acquire cache");
-    cache.load_apps()
+    let hidden = blocklist(&cache);
+    let mut apps = cache.load_apps();
+    apps.retain(|app| !hidden.contains(&app.appid));
+    apps
+}
+
+/// The ids of the apps hidden by the blocklist.
+fn blocklist(cache: &Cache) -> HashSet<String> {
+    match cache.hidden_apps() {
+        Ok(hidden) => hidden.into_iter().collect(),
+        Err(err) => {
+            eprintln!("Failed to load hidden apps: {err}");
+            HashSet::new()
+        }
+    }
+}
+
+/// Hide `app` by adding it to the blocklist.
+fn hide_in_blocklist(app: &AppDescriptor) -> anyhow::Result<()> {
+    let mut cache = CACHE
+        .lock()
+        .map_err(|_| anyhow::anyhow!("Failed to acquire cache"))?;
+    cache.set_hidden(&app.appid, true)
+}
+
+/// Hide `app` by writing a desktop entry override with `NoDisplay=true`.
+fn hide_with_override(app: &AppDescriptor) -> anyhow::Result<()> {
+    let entry = installed_entries()
+        .into_iter()
+        .find(|entry| entry.appid == app.appid)
+        .with_context(|| format!("No desktop entry for {}", app.appid))?;
+    overrides::hide(&app.appid, &entry.path)?;
+    // Drop the app from the cache now, as the launcher may close before the watcher reports it
+    let mut cache = CACHE
+        .lock()
+        .map_err(|_| anyhow::anyhow!("Failed to acquire cache"))?;
+    cache.apply_changes(Vec::new(), std::slice::from_ref(&app.appid))?;
+    Ok(())
+}
+
+/// The apps hidden by the blocklist or by an override, by title.
+fn hidden_apps() -> Vec<HiddenApp> {
+    let blocklist = blocklist(&CACHE.lock().expect("Failed to acquire cache"));
+    let overrides_dir = overrides::overrides_dir();
+    let mut apps: Vec<HiddenApp> = installed_entries()
+        .into_iter()
+        .filter_map(|entry| {
+            let overridden = overrides_dir
+                .as_ref()
+                .is_some_and(|dir| entry.path.starts_with(dir))
+                && std::fs::read_to_string(&entry.path)
+                    .is_ok_and(|contents| overrides::is_override(&contents));
+            if !overridden && !blocklist.contains(&entry.appid) {
+                return None;
+            }
+            Some(HiddenApp {
+                title: entry
+                    .desktop_entry("Name")
+                    .unwrap_or(&entry.appid)
+                    .to_string(),
+                icon_name: entry.icon().map(str::to_string),
+                override_path: overridden.then(|| entry.path.clone()),
+                appid: entry.appid,
+            })
+        })
+        .collect();
+    apps.sort_by(|a, b| a.title.cmp(&b.title));
+    apps
+}
+
+/// Show a hidden app again.
+fn unhide_app(app: &HiddenApp) -> anyhow::Result<()> {
+    let mut cache = CACHE
+        .lock()
+        .map_err(|_| anyhow::anyhow!("Failed to acquire cache"))?;
+    match &app.override_path {
+        Some(path) => {
+            overrides::unhide(path)?;
+            cache.refresh()
+        }
+        None => cache.set_hidden(&app.appid, false),
+    }
+}
+
+fn search_paths() -> &'static SearchPaths {
+    SEARCH_PATHS
+        .get_or_init(|| SearchPaths::new(&[], &[]).expect("default search paths are always valid"))
+}
+
+/// Stream changes to desktop entries in the search paths, patching the cache as they arrive.
+fn watch_apps() -> BoxStream<'static, AppsUpdate> {
+    iced::stream::channel(1, async |mut output| {
+        let events = match watch_desktop_dirs(search_paths().scan_dirs(), DEFAULT_WATCH_DEBOUNCE) {
+            Ok(events) => events,
+            Err(err) => {
+                eprintln!("Failed to watch application directories: {err}");
+                return;
+            }
+        };
+
+        // The watcher blocks, so keep it off the async executor
+        std::thread::spawn(move || {
+            while let Ok(event) = events.recv() {
+                match apps_update(event) {
+                    Ok(update) => {
+                        if iced::futures::executor::block_on(output.send(update)).is_err() {
+                            return;
+                        }
+                    }
+                    Err(err) => eprintln!("Failed to update apps: {err}"),
+                }
+            }
+        });
+    })
+    .boxed()
+}
+
+/// Apply a batch of filesystem changes to the cache, returning what changed in the app list.
+fn apps_update(event: WatchEvent) -> anyhow::Result<AppsUpdate> {
+    let mut cache = CACHE
+        .lock()
+        .map_err(|_| anyhow::anyhow!("Failed to acquire cache"))?;
+
+    match event {
+        WatchEvent::Changed(paths) => {
+            let ids = changed_desktop_ids(&paths);
+            let updated = app_descriptors(desktop_entries_for_ids(
+                search_paths().scan_dirs(),
+                &ids,
+                &get_languages_from_env(),
+                is_excluded,
+            ));
+            let removed: Vec<String> = ids
+                .into_iter()
+                .filter(|id| !updated.iter().any(|app| &app.appid == id))
+                .collect();
+            let updated = cache.apply_changes(updated, &removed)?;
+            Ok(without_hidden(&cache, updated, removed))
+        }
+        WatchEvent::Rescan => {
+            let before: HashSet<String> =
+                cache.load_apps().into_iter().map(|app| app.appid).collect();
+            cache.refresh()?;
+            let updated = cache.load_apps();
+            let removed = before
+                .into_iter()
+                .filter(|id| !updated.iter().any(|app| &app.appid == id))
+                .collect();
+            Ok(without_hidden(&cache, updated, removed))
+        }
+    }
+}
+
+/// An update of the app list that removes the apps on the blocklist rather than showing them.
+fn without_hidden(
+    cache: &Cache,
+    updated: Vec<AppDescriptor>,
+    mut removed: Vec<String>,
+) -> AppsUpdate {
+    let hidden = blocklist(cache);
+    let (hidden, updated): (Vec<_>, Vec<_>) = updated
+        .into_iter()
+        .partition(|app| hidden.contains(&app.appid));
+    removed.extend(hidden.into_iter().map(|app| app.appid));
+    AppsUpdate { updated, removed }
+}
+
+/// Map changed desktop file paths to their desktop-file IDs within the search paths.
+fn changed_desktop_ids(paths: &[PathBuf]) -> HashSet<String> {
+    let search_paths = search_paths();
+    paths
+        .iter()
+        .filter(|path| !search_paths.is_excluded(path))
+        .filter_map(|path| {
+            search_paths
+                .scan_dirs()
+                .find_map(|base| desktop_file_id(&base, path))
+        })
+        .collect()
 }
 
 /// Load DesktopEntry's from `DesktopIter`
 fn find_all_apps() -> Vec<AppDescriptor> {
     let locales = get_languages_from_env();
+    let mut app_list = app_descriptors(desktop_entries(
+        search_paths().scan_dirs(),
+        &locales,
+        is_excluded,
+    ));
+
+    app_list.sort_by(|a, b| a.title.cmp(&b.title));
+
+    app_list
+}
+
+/// The desktop entries in the search paths, including those not shown in the launcher.
+fn installed_entries() -> Vec<DesktopEntry> {
+    desktop_entries(
+        search_paths().scan_dirs(),
+        &get_languages_from_env(),
+        is_excluded,
+    )
+}
+
+/// Whether `path` is in a directory excluded from the desktop entry scan.
+fn is_excluded(path: &Path) -> bool {
+    search_paths().is_excluded(path)
+}
 
-    let app_list_iter = Iter::new(default_paths())
-        .entries(Some(&locales))
+/// Convert desktop entries into launchable apps, dropping those that should not be shown.
+fn app_descriptors(entries: Vec<DesktopEntry>) -> Vec<AppDescriptor> {
+    let app_list_iter = entries
+        .into_iter()
         .filter(|entry| !entry.no_display())
         .filter(|entry| entry.desktop_entry("Name").is_some()) // Ignore apps w/out titles
         .filter(|entry| entry.exec().is_some());
 
     // If current desktop is known, filter items that only apply to that desktop
-    let mut app_list = if let Some(current_desktop) = current_desktop() {
+    if let Some(current_desktop) = current_desktop() {
         app_list_iter
             .filter(|entry| matching_show_in_filter(entry, &current_desktop))
             .filter(|entry| matching_no_show_in_filter(entry, &current_desktop))
@@ -236,11 +973,7 @@ fn find_all_apps() -> Vec<AppDescriptor> {
             .collect::<Vec<_>>()
     } else {
         app_list_iter.map(AppDescriptor::from).collect::<Vec<_>>()
-    };
-
-    app_list.sort_by(|a, b| a.title.cmp(&b.title));
-
-    app_list
+    }
 }
 
 // Return true if the entry and current desktop have a matching element, or if no desktop is available or the entry has no desktop spec.  False otherwise.
@@ -0,0 +1,455 @@
+//! The applications that open files and URLs: the defaults and associations chosen for MIME types
+//! and URL schemes in `mimeapps.list`, and how an app's command line is filled in.
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use freedesktop_desktop_entry::{current_desktop, DesktopEntry};
+
+use crate::mime_types::MimeDatabase;
+
+/// Read an XDG base directory variable, falling back to `default` under the home directory.
+fn xdg_home(var: &str, default: &str) -> Option<PathBuf> {
+    std::env::var_os(var)
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(default)))
+}
+
+/// Read an XDG directory list variable, falling back to `default`.
+fn xdg_dirs(var: &str, default: &str) -> Vec<PathBuf> {
+    let value = std::env::var(var)
+        .ok()
+        .filter(|value| !value.is_empty())
+        .unwrap_or_else(|| default.to_string());
+    value
+        .split(':')
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .collect()
+}
+
+/// The user's XDG data directory.
+pub fn data_home() -> Option<PathBuf> {
+    xdg_home("XDG_DATA_HOME", ".local/share")
+}
+
+/// The XDG data directories, most important first.
+pub fn data_dirs() -> Vec<PathBuf> {
+    data_home()
+        .into_iter()
+        .chain(xdg_dirs("XDG_DATA_DIRS", "/usr/local/share:/usr/share"))
+        .collect()
+}
+
+/// The `mimeapps.list` files, most important first.  In each directory, the lists of the current
+/// desktops, such as `gnome-mimeapps.list`, come before the shared one.
+fn mimeapps_lists(desktops: &[String]) -> Vec<PathBuf> {
+    let config_dirs = xdg_home("XDG_CONFIG_HOME", ".config")
+        .into_iter()
+        .chain(xdg_dirs("XDG_CONFIG_DIRS", "/etc/xdg"));
+    let data_dirs = data_dirs().into_iter().map(|dir| dir.join("applications"));
+    config_dirs
+        .chain(data_dirs)
+        .flat_map(|dir| {
+            desktops
+                .iter()
+                .map(|desktop| format!("{desktop}-mimeapps.list"))
+                .chain(["mimeapps.list".to_string()])
+                .map(move |name| dir.join(name))
+        })
+        .collect()
+}
+
+/// The associations of one `mimeapps.list` file, by MIME type, as app IDs.
+#[derive(Debug, Clone, Default)]
+struct MimeAppsList {
+    defaults: HashMap<String, Vec<String>>,
+    added: HashMap<String, Vec<String>>,
+    removed: HashMap<String, Vec<String>>,
+}
+
+impl MimeAppsList {
+    fn parse(contents: &str) -> Self {
+        let mut list = MimeAppsList::default();
+        let mut group = None;
+        for line in contents.lines().map(str::trim) {
+            if line.starts_with('[') {
+                group = match line {
+                    "[Default Applications]" => Some(&mut list.defaults),
+                    "[Added Associations]" => Some(&mut list.added),
+                    "[Removed Associations]" => Some(&mut list.removed),
+                    _ => None,
+                };
+                continue;
+            }
+            let (Some(group), Some((mime_type, ids))) = (group.as_mut(), line.split_once('='))
+            else {
+                continue;
+            };
+            // Desktop-file IDs name the file, but Elbey's app IDs leave off the suffix
+            let ids = ids
+                .split(';')
+                .map(str::trim)
+                .filter(|id| !id.is_empty())
+                .map(|id| id.strip_suffix(".desktop").unwrap_or(id).to_string());
+            group
+                .entry(mime_type.trim().to_string())
+                .or_default()
+                .extend(ids);
+        }
+        list
+    }
+}
+
+/// The associations between MIME types and apps chosen in the `mimeapps.list` files, following
+/// the [MIME applications spec](https://specifications.freedesktop.org/mime-apps-spec/latest/).
+#[derive(Debug, Clone, Default)]
+pub struct Associations {
+    /// The lists, most important first
+    lists: Vec<MimeAppsList>,
+}
+
+impl Associations {
+    /// Read the `mimeapps.list` files of the user, the current desktops and the system.
+    pub fn load() -> Self {
+        let desktops = current_desktop().unwrap_or_default();
+        let contents: Vec<String> = mimeapps_lists(&desktops)
+            .into_iter()
+            .filter_map(|path| std::fs::read_to_string(path).ok())
+            .collect();
+        Self::parse(&contents)
+    }
+
+    /// Parse the contents of `mimeapps.list` files, most important first.
+    pub fn parse<S: AsRef<str>>(contents: &[S]) -> Self {
+        Associations {
+            lists: contents
+                .iter()
+                .map(|contents| MimeAppsList::parse(contents.as_ref()))
+                .collect(),
+        }
+    }
+
+    /// Whether the association of `app` with `mime_type` was removed by the list at `index` or a
+    /// more important one.
+    fn is_removed(&self, index: usize, mime_type: &str, app: &str) -> bool {
+        self.lists[..=index].iter().any(|list| {
+            list.removed
+                .get(mime_type)
+                .is_some_and(|apps| apps.iter().any(|id| id == app))
+        })
+    }
+
+    /// The apps listed in one group of every list for `mime_type`, in order, without those whose
+    /// association was removed.
+    fn listed(
+        &self,
+        mime_type: &str,
+        group: fn(&MimeAppsList) -> &HashMap<String, Vec<String>>,
+    ) -> Vec<String> {
+        let mut apps: Vec<String> = Vec::new();
+        for (index, list) in self.lists.iter().enumerate() {
+            for app in group(list).get(mime_type).into_iter().flatten() {
+                if !apps.contains(app) && !self.is_removed(index, mime_type, app) {
+                    apps.push(app.clone());
+                }
+            }
+        }
+        apps
+    }
+
+    /// The default apps for `mime_type`, most preferred first.
+    pub fn defaults(&self, mime_type: &str) -> Vec<String> {
+        self.listed(mime_type, |list| &list.defaults)
+    }
+
+    /// The apps associated with `mime_type` by `[Added Associations]`, most preferred first.
+    pub fn added(&self, mime_type: &str) -> Vec<String> {
+        self.listed(mime_type, |list| &list.added)
+    }
+
+    /// Whether any list removed the association of `app` with `mime_type`.
+    pub fn removed(&self, mime_type: &str, app: &str) -> bool {
+        !self.lists.is_empty() && self.is_removed(self.lists.len() - 1, mime_type, app)
+    }
+
+    /// The app that opens a file of type `types[0]`, given the types it is a kind of, most
+    /// specific first.  A default app for any of the types comes first, then the apps associated
+    /// with them in the lists or by their own `MimeType=` key.
+    pub fn default_app<'a>(
+        &self,
+        types: &[String],
+        database: &MimeDatabase,
+        entries: &'a [DesktopEntry],
+    ) -> Option<&'a DesktopEntry> {
+        let installed = |id: &str| {
+            entries
+                .iter()
+                .find(|entry| entry.appid == id && entry.exec().is_some())
+        };
+        let declared = |mime_type: &str| {
+            entries
+                .iter()
+                .filter(move |entry| {
+                    entry.exec().is_some()
+                        && !self.removed(mime_type, &entry.appid)
+                        && entry.mime_type().is_some_and(|declared| {
+                            declared
+                                .iter()
+                                .any(|declared| database.unalias(declared) == mime_type)
+                        })
+                })
+                .map(|entry| entry.appid.clone())
+                .collect::<Vec<_>>()
+        };
+
+        let defaults = types.iter().flat_map(|mime_type| self.defaults(mime_type));
+        let associated = types
+            .iter()
+            .flat_map(|mime_type| self.added(mime_type).into_iter().chain(declared(mime_type)));
+        defaults.chain(associated).find_map(|id| installed(&id))
+    }
+
+    /// The command line that opens `target` with its default app.
+    pub fn open_command(
+        &self,
+        target: &Target,
+        database: &MimeDatabase,
+        entries: &[DesktopEntry],
+    ) -> anyhow::Result<Vec<String>> {
+        let types = database.supertypes(&target.mime_type);
+        let entry = self
+            .default_app(&types, database, entries)
+            .with_context(|| format!("No application opens {}", target.mime_type))?;
+        let path = target
+            .path
+            .as_ref()
+            .map(|path| path.to_string_lossy().into_owned());
+        expand_exec(
+            entry.exec().unwrap_or_default(),
+            path.as_deref(),
+            &target.uri,
+        )
+    }
+}
+
+/// Expand a desktop entry `Exec` line to open `target`: `%f` and `%F` become `path`, or the URI
+/// when there is no local path, `%u` and `%U` become `uri` and `%%` becomes `%`.  Other field
+/// codes are dropped.
+pub fn expand_exec(exec: &str, path: Option<&str>, uri: &str) -> anyhow::Result<Vec<String>> {
+    let file = path.unwrap_or(uri);
+    let args: Vec<String> = shell_words::split(exec)?
+        .into_iter()
+        .filter_map(|arg| match arg.as_str() {
+            "%f" | "%F" => Some(file.to_string()),
+            "%u" | "%U" => Some(uri.to_string()),
+            "%%" => Some("%".to_string()),
+            _ if arg.starts_with('%') => None,
+            _ => Some(expand_embedded(&arg, file, uri)),
+        })
+        .collect();
+    anyhow::ensure!(!args.is_empty(), "Empty command line `{exec}`");
+    Ok(args)
+}
+
+/// Expand the `%f`, `%u` and `%%` codes within an argument in one pass, so that `%%f` stays a
+/// literal `%f`.  Other codes are kept as they are.
+fn expand_embedded(arg: &str, file: &str, uri: &str) -> String {
+    let mut expanded = String::with_capacity(arg.len());
+    let mut chars = arg.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            expanded.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('f') => expanded.push_str(file),
+            Some('u') => expanded.push_str(uri),
+            Some('%') => expanded.push('%'),
+            Some(code) => {
+                expanded.push('%');
+                expanded.push(code);
+            }
+            None => expanded.push('%'),
+        }
+    }
+    expanded
+}
+
+/// Decode the `%XX` escapes of a URI path.
+pub fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| text.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// The `file://` URI of an absolute path.
+pub fn file_uri(path: &Path) -> String {
+    let mut uri = String::from("file://");
+    for byte in path.to_string_lossy().bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    uri
+}
+
+/// A file or URL to open, with the MIME type that chooses its applications.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Target {
+    /// The local path, for files
+    pub path: Option<PathBuf>,
+    pub uri: String,
+    /// The file's MIME type, or `x-scheme-handler/<scheme>` for other URLs
+    pub mime_type: String,
+}
+
+impl Target {
+    /// Parse a path, `file://` URI or URL, detecting the MIME type of files with `database`.
+    pub fn new(target: &str, database: &MimeDatabase) -> anyhow::Result<Self> {
+        let scheme = target
+            .split_once(':')
+            .map(|(scheme, _)| scheme)
+            .filter(|scheme| {
+                scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                    && scheme
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || "+.-".contains(c))
+            });
+        let path = match scheme {
+            Some(scheme) if scheme.eq_ignore_ascii_case("file") => {
+                let path = target[scheme.len() + 1..].trim_start_matches("//");
+                PathBuf::from(format!("/{}", percent_decode(path.trim_start_matches('/'))))
+            }
+            Some(scheme) => {
+                return Ok(Target {
+                    path: None,
+                    uri: target.to_string(),
+                    mime_type: format!("x-scheme-handler/{}", scheme.to_ascii_lowercase()),
+                })
+            }
+            None => std::path::absolute(target)?,
+        };
+        anyhow::ensure!(path.exists(), "{} does not exist", path.display());
+        Ok(Target {
+            uri: file_uri(&path),
+            mime_type: database.type_of(&path),
+            path: Some(path),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(appid: &str, exec: &str, mime_types: &str) -> DesktopEntry {
+        let contents = format!(
+            "[Desktop Entry]\nType=Application\nName={appid}\nExec={exec}\nMimeType={mime_types}\n"
+        );
+        let mut entry =
+            DesktopEntry::from_str(format!("/{appid}.desktop"), &contents, None::<&[&str]>)
+                .unwrap();
+        entry.appid = appid.to_string();
+        entry
+    }
+
+    #[test]
+    fn test_associations_across_lists() {
+        let user = "[Default Applications]\ntext/plain=missing.desktop;vim.desktop\n\n[Removed Associations]\ntext/plain=gedit.desktop;\n";
+        let system = "[Default Applications]\ntext/plain=gedit.desktop;kate.desktop\nx-scheme-handler/https=firefox.desktop\n\n[Added Associations]\nimage/png=gimp.desktop;\n";
+        let associations = Associations::parse(&[user, system]);
+        assert_eq!(
+            associations.defaults("text/plain"),
+            vec!["missing", "vim", "kate"]
+        );
+        assert_eq!(associations.added("image/png"), vec!["gimp"]);
+        assert!(associations.removed("text/plain", "gedit"));
+
+        let database = MimeDatabase::parse("", "", "");
+        let entries = vec![
+            entry("vim", "vim %F", ""),
+            entry("gedit", "gedit %U", "text/plain;"),
+            entry("gimp", "gimp %U", "image/png;"),
+            entry("feh", "feh %f", "image/png;image/jpeg;"),
+            entry("firefox", "firefox %u", "x-scheme-handler/https;"),
+        ];
+        let app = |mime_type: &str| {
+            associations
+                .default_app(&database.supertypes(mime_type), &database, &entries)
+                .map(|entry| entry.appid.as_str())
+        };
+        // The first installed default wins; without one, associations apply
+        assert_eq!(app("text/plain"), Some("vim"));
+        assert_eq!(app("text/markdown"), Some("vim"));
+        assert_eq!(app("image/png"), Some("gimp"));
+        assert_eq!(app("image/jpeg"), Some("feh"));
+        assert_eq!(app("video/mp4"), None);
+
+        let target = Target {
+            path: None,
+            uri: "https://example.com".to_string(),
+            mime_type: "x-scheme-handler/https".to_string(),
+        };
+        assert_eq!(
+            associations
+                .open_command(&target, &database, &entries)
+                .unwrap(),
+            vec!["firefox", "https://example.com"]
+        );
+    }
+
+    #[test]
+    fn test_exec_expansion() {
+        assert_eq!(
+            expand_exec("firefox --new-window %u", None, "https://example.com").unwrap(),
+            vec!["firefox", "--new-window", "https://example.com"]
+        );
+        assert_eq!(
+            expand_exec("gimp %F %i", Some("/tmp/a b.png"), "file:///tmp/a%20b.png").unwrap(),
+            vec!["gimp", "/tmp/a b.png"]
+        );
+        assert_eq!(
+            expand_exec("printf %% 100%%-%%f=%f", Some("/tmp/a"), "file:///tmp/a").unwrap(),
+            vec!["printf", "%", "100%-%f=/tmp/a"]
+        );
+    }
+
+    #[test]
+    fn test_targets() {
+        let database = MimeDatabase::default();
+        let url = Target::new("https://example.com/a", &database).unwrap();
+        assert_eq!(url.mime_type, "x-scheme-handler/https");
+        assert_eq!(url.path, None);
+
+        let dir = std::env::temp_dir().join(format!("elbey mime {}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let target = Target::new(&file_uri(&dir), &database).unwrap();
+        assert_eq!(target.path.as_deref(), Some(dir.as_path()));
+        assert_eq!(target.mime_type, "inode/directory");
+        assert!(target.uri.contains("elbey%20mime"));
+        assert!(Target::new("/no/such/elbey/file", &database).is_err());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
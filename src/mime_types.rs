@@ -0,0 +1,208 @@
+//! MIME type detection for local files with the shared-mime-info database: file names are matched
+//! against its `globs2` patterns, falling back to a look at the content.
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use glob::{MatchOptions, Pattern};
+
+use crate::mime_apps;
+
+/// A file name pattern from `globs2`.
+#[derive(Debug, Clone)]
+struct Glob {
+    weight: u32,
+    mime_type: String,
+    pattern: Pattern,
+    /// Whether the pattern has no wildcards, which wins over any weight
+    literal: bool,
+    case_sensitive: bool,
+}
+
+/// The parts of the shared-mime-info database used to detect and relate MIME types.
+#[derive(Debug, Clone, Default)]
+pub struct MimeDatabase {
+    globs: Vec<Glob>,
+    /// The parent types of each type
+    subclasses: HashMap<String, Vec<String>>,
+    /// The canonical name of each alias
+    aliases: HashMap<String, String>,
+}
+
+impl MimeDatabase {
+    /// Load the database from the `mime` directory of each XDG data directory.
+    pub fn load() -> Self {
+        let dirs: Vec<PathBuf> = mime_apps::data_dirs()
+            .into_iter()
+            .map(|dir| dir.join("mime"))
+            .collect();
+        let read = |name: &str| {
+            dirs.iter()
+                .filter_map(|dir| std::fs::read_to_string(dir.join(name)).ok())
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        Self::parse(&read("globs2"), &read("subclasses"), &read("aliases"))
+    }
+
+    /// Parse the contents of the `globs2`, `subclasses` and `aliases` files.
+    pub fn parse(globs2: &str, subclasses: &str, aliases: &str) -> Self {
+        let globs = globs2
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .filter_map(|line| {
+                let mut fields = line.split(':');
+                let weight = fields.next()?.parse().ok()?;
+                let mime_type = fields.next()?.to_string();
+                let glob = fields.next()?;
+                let case_sensitive = fields.next().is_some_and(|flags| flags.contains("cs"));
+                Some(Glob {
+                    weight,
+                    mime_type,
+                    pattern: Pattern::new(glob).ok()?,
+                    literal: !glob.contains(['*', '?', '[']),
+                    case_sensitive,
+                })
+            })
+            .collect();
+
+        let mut parents: HashMap<String, Vec<String>> = HashMap::new();
+        for (child, parent) in subclasses.lines().filter_map(|line| line.split_once(' ')) {
+            parents
+                .entry(child.to_string())
+                .or_default()
+                .push(parent.trim().to_string());
+        }
+        let aliases = aliases
+            .lines()
+            .filter_map(|line| line.split_once(' '))
+            .map(|(alias, name)| (alias.to_string(), name.trim().to_string()))
+            .collect();
+
+        MimeDatabase {
+            globs,
+            subclasses: parents,
+            aliases,
+        }
+    }
+
+    /// The canonical name of `mime_type`.
+    pub fn unalias<'a>(&'a self, mime_type: &'a str) -> &'a str {
+        self.aliases
+            .get(mime_type)
+            .map_or(mime_type, String::as_str)
+    }
+
+    /// The MIME type of the file name `name` according to its patterns, if one matches.
+    fn type_of_name(&self, name: &str) -> Option<&str> {
+        let options = |case_sensitive| MatchOptions {
+            case_sensitive,
+            ..MatchOptions::new()
+        };
+        self.globs
+            .iter()
+            .filter(|glob| {
+                glob.pattern
+                    .matches_with(name, options(glob.case_sensitive))
+            })
+            .max_by_key(|glob| {
+                let length = glob.pattern.as_str().len();
+                (glob.literal, glob.weight, glob.case_sensitive, length)
+            })
+            .map(|glob| glob.mime_type.as_str())
+    }
+
+    /// The MIME type of the file at `path`.
+    pub fn type_of(&self, path: &Path) -> String {
+        if path.is_dir() {
+            return "inode/directory".to_string();
+        }
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if let Some(mime_type) = self.type_of_name(&name) {
+            return mime_type.to_string();
+        }
+        // Without a known extension, files of readable text are plain text
+        let mut head = Vec::new();
+        let text = std::fs::File::open(path)
+            .and_then(|file| file.take(512).read_to_end(&mut head))
+            .is_ok_and(|_| {
+                !head.contains(&0)
+                    && std::str::from_utf8(&head)
+                        .map_or_else(|err| err.error_len().is_none(), |_| true)
+            });
+        if text {
+            "text/plain"
+        } else {
+            "application/octet-stream"
+        }
+        .to_string()
+    }
+
+    /// `mime_type` followed by the types it is a kind of, most specific first: its parents, plain
+    /// text for any text type, and arbitrary data for any file.
+    pub fn supertypes(&self, mime_type: &str) -> Vec<String> {
+        let mut types = vec![self.unalias(mime_type).to_string()];
+        let mut next = 0;
+        while let Some(current) = types.get(next).cloned() {
+            next += 1;
+            let mut parents = self.subclasses.get(&current).cloned().unwrap_or_default();
+            if current.starts_with("text/") {
+                parents.push("text/plain".to_string());
+            }
+            for parent in parents {
+                let parent = self.unalias(&parent).to_string();
+                if !types.contains(&parent) {
+                    types.push(parent);
+                }
+            }
+        }
+        let is_file = !["inode/", "x-scheme-handler/"]
+            .iter()
+            .any(|prefix| mime_type.starts_with(prefix));
+        let any = "application/octet-stream".to_string();
+        if is_file && !types.contains(&any) {
+            types.push(any);
+        }
+        types
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_matching_and_supertypes() {
+        let database = MimeDatabase::parse(
+            "# comment\n50:text/x-csrc:*.c\n50:text/x-c++src:*.C:cs\n50:application/gzip:*.gz\n50:application/x-compressed-tar:*.tar.gz\n60:text/x-makefile:Makefile\n10:text/x-makefile:*.mk\n",
+            "application/x-compressed-tar application/gzip\ntext/x-csrc text/plain\n",
+            "application/x-gzip application/gzip\n",
+        );
+        assert_eq!(database.type_of_name("main.c"), Some("text/x-csrc"));
+        assert_eq!(database.type_of_name("MAIN.C"), Some("text/x-c++src"));
+        assert_eq!(
+            database.type_of_name("src.tar.gz"),
+            Some("application/x-compressed-tar")
+        );
+        assert_eq!(database.type_of_name("Makefile"), Some("text/x-makefile"));
+        assert_eq!(database.type_of_name("notes"), None);
+
+        assert_eq!(
+            database.supertypes("application/x-compressed-tar"),
+            vec![
+                "application/x-compressed-tar",
+                "application/gzip",
+                "application/octet-stream"
+            ]
+        );
+        assert_eq!(
+            database.supertypes("text/x-makefile"),
+            vec!["text/x-makefile", "text/plain", "application/octet-stream"]
+        );
+        assert_eq!(database.unalias("application/x-gzip"), "application/gzip");
+        assert_eq!(
+            database.supertypes("x-scheme-handler/https"),
+            vec!["x-scheme-handler/https"]
+        );
+    }
+}
@@ -0,0 +1,59 @@
+//! Delivery of text picked in the launcher, such as a calculation result.
+use std::io::Write;
+use std::process::{exit, Command, Stdio};
+
+use anyhow::Context;
+
+/// Where picked text goes.
+#[derive(Debug, Clone, Default)]
+pub enum Output {
+    /// Print the text to stdout
+    #[default]
+    Stdout,
+    /// Run a command with the text, e.g. `wl-copy`.  A `%s` argument is replaced by the text;
+    /// without one the text is written to the command's stdin.
+    Command(Vec<String>),
+}
+
+impl Output {
+    /// Parse the `--copy-command` option, printing to stdout when it is not given.
+    pub fn new(command: Option<&str>) -> anyhow::Result<Self> {
+        match command {
+            Some(command) => {
+                let args = shell_words::split(command)?;
+                anyhow::ensure!(!args.is_empty(), "Empty copy command");
+                Ok(Output::Command(args))
+            }
+            None => Ok(Output::Stdout),
+        }
+    }
+
+    /// Deliver `text`.  This implementation exits the process once the text is delivered.
+    pub fn emit(&self, text: &str) -> anyhow::Result<()> {
+        match self {
+            Output::Stdout => {
+                let mut stdout = std::io::stdout().lock();
+                writeln!(stdout, "{text}")?;
+                stdout.flush()?;
+            }
+            Output::Command(args) => {
+                let substitutes = args.iter().any(|arg| arg.contains("%s"));
+                let args: Vec<String> = args.iter().map(|arg| arg.replace("%s", text)).collect();
+                let mut child = Command::new(&args[0])
+                    .args(&args[1..])
+                    .stdin(if substitutes {
+                        Stdio::null()
+                    } else {
+                        Stdio::piped()
+                    })
+                    .spawn()
+                    .with_context(|| format!("Failed to run {}", args[0]))?;
+                // Not waited on, as clipboard tools may keep running to serve the selection
+                if let Some(mut stdin) = child.stdin.take() {
+                    stdin.write_all(text.as_bytes())?;
+                }
+            }
+        }
+        exit(0);
+    }
+}
@@ -0,0 +1,125 @@
+//! Desktop entry overrides that hide an app: a copy of its entry in the user's applications
+//! directory with `NoDisplay=true`, which takes the place of the installed entry in every
+//! launcher and menu.  Overrides are marked so they can be told apart from the user's own entries
+//! and undone.
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+
+use crate::mime_apps;
+
+/// Marks an entry hidden by an override, with how to undo it
+const HIDDEN_KEY: &str = "X-Elbey-Hidden";
+/// The override is a copy of an installed entry, removed to show the app again
+const COPIED: &str = "copied";
+/// The override is the user's own entry, edited in place
+const EDITED: &str = "edited";
+
+/// The directory of the user's desktop entries, which take precedence over installed ones.
+pub fn overrides_dir() -> Option<PathBuf> {
+    mime_apps::data_home().map(|dir| dir.join("applications"))
+}
+
+/// Whether the desktop entry `contents` was hidden by an override.
+pub fn is_override(contents: &str) -> bool {
+    marker(contents).is_some()
+}
+
+/// The value of the marker key in the `[Desktop Entry]` group.
+fn marker(contents: &str) -> Option<&str> {
+    let mut in_entry = false;
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            in_entry = line == "[Desktop Entry]";
+        } else if in_entry {
+            if let Some((key, value)) = line.split_once('=') {
+                if key.trim() == HIDDEN_KEY {
+                    return Some(value.trim());
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Rewrite the `[Desktop Entry]` group of `contents`, dropping its `NoDisplay` and marker keys
+/// and adding `added` after the group header.
+fn rewrite(contents: &str, added: &[String]) -> String {
+    let mut lines = Vec::new();
+    let mut in_entry = false;
+    for line in contents.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('[') {
+            in_entry = trimmed == "[Desktop Entry]";
+            lines.push(line.to_string());
+            if in_entry {
+                lines.extend(added.iter().cloned());
+            }
+            continue;
+        }
+        let key = trimmed.split_once('=').map(|(key, _)| key.trim());
+        if in_entry && matches!(key, Some("NoDisplay" | HIDDEN_KEY)) {
+            continue;
+        }
+        lines.push(line.to_string());
+    }
+    lines.join("\n") + "\n"
+}
+
+/// The contents of an override of the entry `contents` that hides it.
+fn hidden_contents(contents: &str, how: &str) -> String {
+    rewrite(
+        contents,
+        &["NoDisplay=true".to_string(), format!("{HIDDEN_KEY}={how}")],
+    )
+}
+
+/// Hide the app `appid` whose entry is at `source` by writing an override, returning its path.
+pub fn hide(appid: &str, source: &Path) -> anyhow::Result<PathBuf> {
+    let dir = overrides_dir().context("No data directory for desktop entries")?;
+    let target = dir.join(format!("{appid}.desktop"));
+    let contents = std::fs::read_to_string(source)
+        .with_context(|| format!("Failed to read {}", source.display()))?;
+    let how = if source == target { EDITED } else { COPIED };
+    std::fs::create_dir_all(&dir)?;
+    std::fs::write(&target, hidden_contents(&contents, how))
+        .with_context(|| format!("Failed to write {}", target.display()))?;
+    Ok(target)
+}
+
+/// Undo the override at `path`, removing a copy or restoring an edited entry.
+pub fn unhide(path: &Path) -> anyhow::Result<()> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    match marker(&contents) {
+        Some(COPIED) => std::fs::remove_file(path)?,
+        Some(_) => std::fs::write(path, rewrite(&contents, &[]))?,
+        None => anyhow::bail!("{} was not hidden by elbey", path.display()),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hidden_contents_round_trip() {
+        let entry = "[Desktop Entry]\nName=Qt Assistant\nNoDisplay=false\nExec=assistant\n\n[Desktop Action new]\nNoDisplay=false\n";
+        let hidden = hidden_contents(entry, EDITED);
+        assert_eq!(
+            hidden,
+            "[Desktop Entry]\nNoDisplay=true\nX-Elbey-Hidden=edited\nName=Qt Assistant\nExec=assistant\n\n[Desktop Action new]\nNoDisplay=false\n"
+        );
+        assert!(is_override(&hidden));
+        assert_eq!(marker(&hidden_contents(entry, COPIED)), Some(COPIED));
+
+        let shown = rewrite(&hidden, &[]);
+        assert!(!is_override(&shown));
+        assert_eq!(
+            shown,
+            "[Desktop Entry]\nName=Qt Assistant\nExec=assistant\n\n[Desktop Action new]\nNoDisplay=false\n"
+        );
+    }
+}
@@ -0,0 +1,238 @@
+//! The item model shared by every kind of list Elbey can show, and the trait that supplies it.
+//!
+//! Each mode of the launcher (desktop apps, `$PATH` commands, ...) is a [`Provider`].  The UI only
+//! deals in [`Item`]s: it filters and renders them, and hands the chosen one back to the provider
+//! that created it.
+use std::any::Any;
+use std::fmt::{self, Debug, Formatter};
+use std::sync::Arc;
+
+use iced::Subscription;
+
+use crate::values::IconHandle;
+
+/// A row in the launcher list.
+#[derive(Clone)]
+pub struct Item {
+    /// Identifies the item within its provider
+    pub id: String,
+    /// Primary text of the row
+    pub title: String,
+    /// Secondary text rendered below the title
+    pub subtitle: Option<String>,
+    /// Icon rendered beside the text; `NotLoaded` renders the fallback icon
+    pub icon: IconHandle,
+    /// Usage count; higher ranks sort first when a list is re-ordered
+    pub rank: usize,
+    /// Position among the pinned items, which list first in this order
+    pub pin: Option<usize>,
+    /// Text matched against case-sensitive queries
+    search_text: String,
+    /// Lowercased `search_text`, matched against the query
+    search_text_lower: String,
+    /// Provider-specific data needed to activate the item
+    data: Option<Arc<dyn Any + Send + Sync>>,
+}
+
+impl Item {
+    pub fn new(id: impl Into<String>, title: impl Into<String>) -> Self {
+        let title = title.into();
+        Item {
+            id: id.into(),
+            search_text: title.clone(),
+            search_text_lower: title.to_lowercase(),
+            title,
+            subtitle: None,
+            icon: IconHandle::NotLoaded,
+            rank: 0,
+            pin: None,
+            data: None,
+        }
+    }
+
+    pub fn with_subtitle(mut self, subtitle: impl Into<String>) -> Self {
+        self.subtitle = Some(subtitle.into());
+        self
+    }
+
+    pub fn with_icon(mut self, icon: IconHandle) -> Self {
+        self.icon = icon;
+        self
+    }
+
+    pub fn with_rank(mut self, rank: usize) -> Self {
+        self.rank = rank;
+        self
+    }
+
+    pub fn with_pin(mut self, pin: Option<usize>) -> Self {
+        self.pin = pin;
+        self
+    }
+
+    /// Make the item match queries against `keywords` as well as its title.
+    pub fn with_keywords<S: AsRef<str>>(mut self, keywords: impl IntoIterator<Item = S>) -> Self {
+        for keyword in keywords {
+            // Separate fields so a query cannot match across them
+            self.search_text.push('\n');
+            self.search_text.push_str(keyword.as_ref());
+            self.search_text_lower.push('\n');
+            self.search_text_lower
+                .push_str(&keyword.as_ref().to_lowercase());
+        }
+        self
+    }
+
+    pub fn with_data<T: Any + Send + Sync>(mut self, data: T) -> Self {
+        self.data = Some(Arc::new(data));
+        self
+    }
+
+    /// The provider-specific data attached with [`Item::with_data`], if it is a `T`.
+    pub fn data<T: Any>(&self) -> Option<&T> {
+        self.data.as_deref()?.downcast_ref()
+    }
+
+    /// Returns true if the lowercased `query` occurs in the title or a keyword.
+    pub fn matches(&self, query_lower: &str) -> bool {
+        self.search_text_lower.contains(query_lower)
+    }
+
+    /// Returns true if `query` occurs in the title or a keyword, comparing case.
+    pub fn matches_case_sensitive(&self, query: &str) -> bool {
+        self.search_text.contains(query)
+    }
+}
+
+impl Debug for Item {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Item")
+            .field("id", &self.id)
+            .field("title", &self.title)
+            .field("subtitle", &self.subtitle)
+            .field("rank", &self.rank)
+            .field("pin", &self.pin)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The items a provider lists, with text to show the user alongside them.
+#[derive(Debug, Clone, Default)]
+pub struct Listing {
+    pub items: Vec<Item>,
+    /// A notice or error shown above the list
+    pub message: Option<String>,
+    /// Replaces the hint in the entry box
+    pub prompt: Option<String>,
+}
+
+impl From<Vec<Item>> for Listing {
+    fn from(items: Vec<Item>) -> Self {
+        Listing {
+            items,
+            ..Listing::default()
+        }
+    }
+}
+
+/// What happens after a provider acts on the user's choice.
+#[derive(Debug, Clone)]
+pub enum Activation {
+    /// The choice was acted on.  Providers that start a program exit the launcher themselves.
+    Done,
+    /// Keep the launcher open and show a new listing in place of the provider's items.
+    Replace(Listing),
+}
+
+/// Changes to a provider's items while the launcher is open.
+#[derive(Debug, Clone, Default)]
+pub struct ItemsUpdate {
+    /// Items that were added or modified
+    pub updated: Vec<Item>,
+    /// Ids of items that no longer exist
+    pub removed: Vec<String>,
+}
+
+/// A source of items and the actions taken on them.
+pub trait Provider: Debug + Send + Sync {
+    /// Short name shown on the tab indicator and as the default hint.
+    fn name(&self) -> &str;
+
+    /// Load the items to list.  Called off the UI thread.
+    fn load(&self) -> Listing;
+
+    /// Items computed from the query, listed above the loaded items without filtering.
+    fn query(&self, _query: &str) -> Vec<Item> {
+        Vec::new()
+    }
+
+    /// Whether the query is addressed to this provider alone, such as a typed path.  Only the
+    /// provider's query items are then listed.
+    fn owns_query(&self, _query: &str) -> bool {
+        false
+    }
+
+    /// The query that Tab replaces the entry with when `item` is selected, if it completes to one.
+    fn complete(&self, _item: &Item) -> Option<String> {
+        None
+    }
+
+    /// Take the primary action on an item the user chose.
+    fn activate(&self, item: &Item) -> anyhow::Result<Activation>;
+
+    /// Take the secondary action on an item, chosen with Ctrl+Enter.  Without one, the primary
+    /// action is taken.
+    fn activate_alternate(&self, item: &Item) -> anyhow::Result<Activation> {
+        self.activate(item)
+    }
+
+    /// Act on the typed query rather than the selected item, e.g. to run a typed command.
+    /// Returns `None` to activate the selection instead.
+    fn activate_query(
+        &self,
+        _query: &str,
+        _selected: Option<&Item>,
+    ) -> Option<anyhow::Result<Activation>> {
+        None
+    }
+
+    /// Changes to apply to the loaded items while the launcher is open.
+    fn updates(&self) -> Option<Subscription<ItemsUpdate>> {
+        None
+    }
+
+    /// Take the primary action on several items the user marked, in the order they were marked.
+    fn activate_all(&self, items: &[&Item]) -> anyhow::Result<Activation> {
+        let mut activation = Activation::Done;
+        for item in items {
+            activation = self.activate(item)?;
+        }
+        Ok(activation)
+    }
+
+    /// Pin or unpin an item, chosen with Ctrl+P.  Returns the ids of the pinned items in order,
+    /// or `None` if the provider does not pin items.
+    fn toggle_pin(&self, _item: &Item) -> Option<anyhow::Result<Vec<String>>> {
+        None
+    }
+
+    /// Move a pinned item `delta` places, chosen with Ctrl+Up and Ctrl+Down.  Returns the ids of
+    /// the pinned items in order, or `None` if the provider does not pin items.
+    fn move_pin(&self, _item: &Item, _delta: i32) -> Option<anyhow::Result<Vec<String>>> {
+        None
+    }
+
+    /// Hide an item from future listings, chosen with Ctrl+H.  Returns `None` if the provider
+    /// does not hide items.
+    fn hide(&self, _item: &Item) -> Option<anyhow::Result<()>> {
+        None
+    }
+
+    /// Queries entered before, most recent first, recalled with Up on an empty query.
+    fn history(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Called with the provider's current items when the launcher closes without acting.
+    fn close(&self, _items: &[Item]) {}
+}
@@ -0,0 +1,19 @@
+//! The built-in [`Provider`](crate::provider::Provider)s, one per launcher mode.
+pub mod apps;
+pub mod calc;
+pub mod dmenu;
+pub mod emoji;
+pub mod files;
+pub mod hidden;
+pub mod man;
+pub mod open_with;
+pub mod pass;
+pub mod path;
+pub mod processes;
+pub mod recent;
+pub mod run;
+pub mod script;
+pub mod session;
+pub mod shell;
+pub mod ssh;
+pub mod web;
@@ -0,0 +1,169 @@
+//! Desktop apps, as described by [XDG desktop entries](https://www.freedesktop.org/wiki/Specifications/desktop-entry-spec/).
+use elbey_cache::AppDescriptor;
+use iced::futures::stream::BoxStream;
+use iced::Subscription;
+
+use crate::provider::{Activation, Item, ItemsUpdate, Listing, Provider};
+use crate::CACHE;
+
+/// Key of the pinned apps
+const PIN_MODE: &str = "drun";
+
+/// A function that replaces the pinned items of a mode
+type SavePins = fn(&str, &[String]) -> anyhow::Result<()>;
+
+/// Desktop entry changes observed on disk while the launcher is open.
+#[derive(Debug, Clone, Default)]
+pub struct AppsUpdate {
+    /// Apps that were added or modified
+    pub updated: Vec<AppDescriptor>,
+    /// App ids that no longer have a visible entry
+    pub removed: Vec<String>,
+}
+
+/// Lists desktop apps and launches the chosen one.
+#[derive(Debug)]
+pub struct AppsProvider {
+    /// A function that returns a list of `DesktopEntry`s
+    loader: fn() -> Vec<AppDescriptor>,
+    /// A function that launches a process from a `DesktopEntry`
+    launcher: fn(&AppDescriptor) -> anyhow::Result<()>,
+    /// An optional stream of desktop entry changes, applied to the list while it is shown
+    watcher: Option<fn() -> BoxStream<'static, AppsUpdate>>,
+    /// A function that returns the pinned items of a mode, in order
+    pins: fn(&str) -> Vec<String>,
+    /// Without a function to save them, apps cannot be pinned
+    save_pins: Option<SavePins>,
+    /// A function that hides an app from future listings
+    hider: Option<fn(&AppDescriptor) -> anyhow::Result<()>>,
+}
+
+impl AppsProvider {
+    pub fn new(
+        loader: fn() -> Vec<AppDescriptor>,
+        launcher: fn(&AppDescriptor) -> anyhow::Result<()>,
+        watcher: Option<fn() -> BoxStream<'static, AppsUpdate>>,
+    ) -> Self {
+        AppsProvider {
+            loader,
+            launcher,
+            watcher,
+            pins: |_| Vec::new(),
+            save_pins: None,
+            hider: None,
+        }
+    }
+
+    /// Let the user pin apps to the top of the list, keeping the pins with these functions.
+    pub fn with_pins(mut self, pins: fn(&str) -> Vec<String>, save_pins: SavePins) -> Self {
+        self.pins = pins;
+        self.save_pins = Some(save_pins);
+        self
+    }
+
+    /// Let the user hide apps with `hider`.
+    pub fn with_hider(mut self, hider: fn(&AppDescriptor) -> anyhow::Result<()>) -> Self {
+        self.hider = Some(hider);
+        self
+    }
+
+    /// Apply `change` to the pinned apps and save them.
+    fn change_pins(
+        &self,
+        change: impl FnOnce(&mut Vec<String>),
+    ) -> Option<anyhow::Result<Vec<String>>> {
+        let save_pins = self.save_pins?;
+        let mut pins = (self.pins)(PIN_MODE);
+        change(&mut pins);
+        Some(save_pins(PIN_MODE, &pins).map(|_| pins))
+    }
+}
+
+/// Wrap an app in a list item, keeping the descriptor for activation.
+pub fn app_item(app: AppDescriptor) -> Item {
+    Item::new(app.appid.clone(), app.title.clone())
+        .with_icon(app.icon_handle.clone())
+        .with_rank(app.exec_count)
+        .with_data(app)
+}
+
+fn items_update(update: AppsUpdate) -> ItemsUpdate {
+    ItemsUpdate {
+        updated: update.updated.into_iter().map(app_item).collect(),
+        removed: update.removed,
+    }
+}
+
+fn persist_cache_snapshot(apps: &[AppDescriptor]) {
+    if let Ok(mut cache) = CACHE.lock() {
+        if let Err(e) = cache.save_snapshot(apps) {
+            eprintln!("Failed to persist cache snapshot: {e}");
+        }
+    }
+}
+
+impl Provider for AppsProvider {
+    fn name(&self) -> &str {
+        "drun"
+    }
+
+    fn load(&self) -> Listing {
+        let pins = (self.pins)(PIN_MODE);
+        let mut items: Vec<Item> = (self.loader)()
+            .into_iter()
+            .map(|app| {
+                let pin = pins.iter().position(|pin| *pin == app.appid);
+                app_item(app).with_pin(pin)
+            })
+            .collect();
+        // Pinned apps first, keeping the usage order of the others
+        items.sort_by_key(|item| item.pin.unwrap_or(usize::MAX));
+        items.into()
+    }
+
+    fn activate(&self, item: &Item) -> anyhow::Result<Activation> {
+        if let Some(app) = item.data::<AppDescriptor>() {
+            (self.launcher)(app)?;
+        }
+        Ok(Activation::Done)
+    }
+
+    fn toggle_pin(&self, item: &Item) -> Option<anyhow::Result<Vec<String>>> {
+        self.change_pins(|pins| match pins.iter().position(|pin| *pin == item.id) {
+            Some(position) => {
+                pins.remove(position);
+            }
+            None => pins.push(item.id.clone()),
+        })
+    }
+
+    fn move_pin(&self, item: &Item, delta: i32) -> Option<anyhow::Result<Vec<String>>> {
+        self.change_pins(|pins| {
+            if let Some(position) = pins.iter().position(|pin| *pin == item.id) {
+                let target = (position as i32 + delta).clamp(0, pins.len() as i32 - 1);
+                let pin = pins.remove(position);
+                pins.insert(target as usize, pin);
+            }
+        })
+    }
+
+    fn hide(&self, item: &Item) -> Option<anyhow::Result<()>> {
+        let hider = self.hider?;
+        let app = item.data::<AppDescriptor>()?;
+        Some(hider(app))
+    }
+
+    fn updates(&self) -> Option<Subscription<ItemsUpdate>> {
+        self.watcher
+            .map(|watcher| Subscription::run(watcher).map(items_update))
+    }
+
+    fn close(&self, items: &[Item]) {
+        let apps: Vec<AppDescriptor> = items
+            .iter()
+            .filter_map(|item| item.data::<AppDescriptor>())
+            .cloned()
+            .collect();
+        persist_cache_snapshot(&apps);
+    }
+}
@@ -0,0 +1,43 @@
+//! Results computed from the query, such as arithmetic.
+use crate::calc::{evaluate, format_number};
+use crate::output::Output;
+use crate::provider::{Activation, Item, Listing, Provider};
+use crate::units::convert;
+
+/// Shows the value of an arithmetic or unit conversion query and outputs it when chosen.
+#[derive(Debug)]
+pub struct CalcProvider {
+    output: Output,
+}
+
+impl CalcProvider {
+    pub fn new(output: Output) -> Self {
+        CalcProvider { output }
+    }
+}
+
+impl Provider for CalcProvider {
+    fn name(&self) -> &str {
+        "calc"
+    }
+
+    fn load(&self) -> Listing {
+        Listing::default()
+    }
+
+    fn query(&self, query: &str) -> Vec<Item> {
+        let result = evaluate(query).map(format_number).or_else(|| {
+            convert(query).map(|(value, unit)| format!("{} {unit}", format_number(value)))
+        });
+        result
+            .map(|result| {
+                vec![Item::new(result.clone(), result).with_subtitle(format!("{} =", query.trim()))]
+            })
+            .unwrap_or_default()
+    }
+
+    fn activate(&self, item: &Item) -> anyhow::Result<Activation> {
+        self.output.emit(&item.title)?;
+        Ok(Activation::Done)
+    }
+}
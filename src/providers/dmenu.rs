@@ -0,0 +1,190 @@
+//! Lines read from stdin, printing the chosen ones like `dmenu`.
+//!
+//! Lines are either plain text or, with `--json`, JSON objects describing richer items:
+//!
+//! ```json
+//! {"title": "staging", "subtitle": "k8s context", "icon": "kubernetes", "keywords": ["stg"], "id": 2}
+//! ```
+//!
+//! Only `title` is required.  `icon` is an icon theme name or an absolute path.  The chosen object
+//! is printed back as it was read, so fields such as `id` can carry whatever the script needs.
+use std::io::Write;
+use std::process::exit;
+
+use elbey_cache::load_icon;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::provider::{Activation, Item, Listing, Provider};
+
+/// Exit status when an item was selected.
+pub const EXIT_SELECTED: i32 = 0;
+/// Exit status when the launcher was closed without a selection.
+pub const EXIT_CANCELLED: i32 = 1;
+/// Exit status when text matching no item was entered.
+pub const EXIT_CUSTOM: i32 = 2;
+
+/// How the lines on stdin describe items.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Each line is an item's title
+    Text,
+    /// Each line is a JSON object with a title and optional subtitle, icon, keywords and id
+    Json,
+}
+
+/// The fields of a JSON line that affect how an item is shown and matched.
+#[derive(Debug, Deserialize)]
+struct JsonItem {
+    title: String,
+    subtitle: Option<String>,
+    icon: Option<String>,
+    #[serde(default)]
+    keywords: Vec<String>,
+}
+
+/// Lists lines given on stdin and prints the chosen ones to stdout.
+#[derive(Debug)]
+pub struct DmenuProvider {
+    lines: Vec<String>,
+    format: Format,
+    /// Print the index of the chosen line rather than its text
+    print_index: bool,
+}
+
+impl DmenuProvider {
+    pub fn new(lines: Vec<String>, format: Format, print_index: bool) -> Self {
+        DmenuProvider {
+            lines,
+            format,
+            print_index,
+        }
+    }
+
+    /// The text printed for an item: its line, or its zero-based index in the input.
+    fn output(&self, item: &Item) -> String {
+        if self.print_index {
+            return item.id.clone();
+        }
+        match item.data::<Value>() {
+            Some(value) => value.to_string(),
+            None => item.title.clone(),
+        }
+    }
+
+    /// Parse a JSON line into an item, keeping the object to print if it is chosen.
+    fn json_item(index: usize, line: &str) -> anyhow::Result<Item> {
+        let value: Value = serde_json::from_str(line)?;
+        let fields = JsonItem::deserialize(&value)?;
+
+        let mut item = Item::new(index.to_string(), fields.title).with_keywords(fields.keywords);
+        if let Some(subtitle) = fields.subtitle {
+            item = item.with_subtitle(subtitle);
+        }
+        if let Some(icon) = fields.icon {
+            item = item.with_icon(load_icon(&icon));
+        }
+        Ok(item.with_data(value))
+    }
+}
+
+fn print_and_exit(lines: &[String], code: i32) -> ! {
+    let mut stdout = std::io::stdout().lock();
+    for line in lines {
+        let _ = writeln!(stdout, "{line}");
+    }
+    let _ = stdout.flush();
+    exit(code)
+}
+
+impl Provider for DmenuProvider {
+    fn name(&self) -> &str {
+        "dmenu"
+    }
+
+    fn load(&self) -> Listing {
+        let lines = self.lines.iter().enumerate();
+        let items: Vec<Item> = match self.format {
+            Format::Text => lines
+                .map(|(index, line)| Item::new(index.to_string(), line.as_str()))
+                .collect(),
+            Format::Json => lines
+                .filter(|(_, line)| !line.trim().is_empty())
+                .filter_map(|(index, line)| match Self::json_item(index, line) {
+                    Ok(item) => Some(item),
+                    Err(err) => {
+                        eprintln!("Skipping invalid JSON on line {}: {err}", index + 1);
+                        None
+                    }
+                })
+                .collect(),
+        };
+        items.into()
+    }
+
+    fn activate(&self, item: &Item) -> anyhow::Result<Activation> {
+        print_and_exit(&[self.output(item)], EXIT_SELECTED)
+    }
+
+    fn activate_all(&self, items: &[&Item]) -> anyhow::Result<Activation> {
+        let lines: Vec<String> = items.iter().map(|item| self.output(item)).collect();
+        print_and_exit(&lines, EXIT_SELECTED)
+    }
+
+    // Text that matches no line is printed as entered, as an object with only a title for JSON
+    fn activate_query(
+        &self,
+        query: &str,
+        selected: Option<&Item>,
+    ) -> Option<anyhow::Result<Activation>> {
+        if selected.is_some() || query.is_empty() {
+            return None;
+        }
+        let output = match self.format {
+            Format::Text => query.to_string(),
+            Format::Json => serde_json::json!({ "title": query }).to_string(),
+        };
+        print_and_exit(&[output], EXIT_CUSTOM)
+    }
+
+    fn close(&self, _items: &[Item]) {
+        exit(EXIT_CANCELLED)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_is_line_or_index() {
+        let lines = vec!["alpha".to_string(), "beta".to_string()];
+        let by_text = DmenuProvider::new(lines.clone(), Format::Text, false);
+        let by_index = DmenuProvider::new(lines, Format::Text, true);
+
+        let items = by_text.load().items;
+        assert_eq!(by_text.output(&items[1]), "beta");
+        assert_eq!(by_index.output(&items[1]), "1");
+    }
+
+    #[test]
+    fn test_json_items_echo_their_object() {
+        let lines = vec![
+            r#"{"title": "staging", "subtitle": "k8s context", "keywords": ["stg"], "id": 7}"#
+                .to_string(),
+            "not json".to_string(),
+            r#"{"title": "production"}"#.to_string(),
+        ];
+        let unit = DmenuProvider::new(lines, Format::Json, false);
+
+        let items = unit.load().items;
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].subtitle.as_deref(), Some("k8s context"));
+        assert!(items[0].matches("stg"));
+        assert_eq!(items[1].id, "2");
+
+        let echoed: Value = serde_json::from_str(&unit.output(&items[0])).unwrap();
+        assert_eq!(echoed["id"], 7);
+        assert_eq!(echoed["title"], "staging");
+    }
+}
@@ -0,0 +1,120 @@
+//! Emoji and other Unicode characters, found by name or keyword.
+//!
+//! The table is embedded from `emoji.tsv`: one character per line with its lowercase Unicode
+//! name and optional keywords separated by `|`, each field separated by a tab.
+use std::collections::HashMap;
+
+use crate::output::Output;
+use crate::provider::{Activation, Item, Listing, Provider};
+
+const TABLE: &str = include_str!("emoji.tsv");
+
+/// Key of the usage counts of picked characters
+const USAGE_MODE: &str = "emoji";
+
+/// Lists characters, most picked first, and outputs the chosen one.
+#[derive(Debug)]
+pub struct EmojiProvider {
+    output: Output,
+    /// A function that returns the usage counts of a mode, keyed by item
+    usage: fn(&str) -> HashMap<String, usize>,
+    /// A function that increments the usage count of an item in a mode
+    record: fn(&str, &str),
+}
+
+impl EmojiProvider {
+    pub fn new(
+        output: Output,
+        usage: fn(&str) -> HashMap<String, usize>,
+        record: fn(&str, &str),
+    ) -> Self {
+        EmojiProvider {
+            output,
+            usage,
+            record,
+        }
+    }
+}
+
+/// Parse the embedded table into items, in table order.
+fn table_items() -> Vec<Item> {
+    TABLE
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\t');
+            let glyph = fields.next()?;
+            let name = fields.next()?;
+            let keywords = fields.next().unwrap_or_default();
+            Some(
+                Item::new(glyph, glyph)
+                    .with_subtitle(name)
+                    .with_keywords(std::iter::once(name).chain(keywords.split('|'))),
+            )
+        })
+        .collect()
+}
+
+impl Provider for EmojiProvider {
+    fn name(&self) -> &str {
+        USAGE_MODE
+    }
+
+    fn load(&self) -> Listing {
+        let counts = (self.usage)(USAGE_MODE);
+        let mut items: Vec<Item> = table_items()
+            .into_iter()
+            .map(|item| {
+                let rank = counts.get(&item.id).copied().unwrap_or(0);
+                item.with_rank(rank)
+            })
+            .collect();
+        // Stable, so characters used equally often keep their table order
+        items.sort_by_key(|item| std::cmp::Reverse(item.rank));
+        items.into()
+    }
+
+    fn activate(&self, item: &Item) -> anyhow::Result<Activation> {
+        (self.record)(USAGE_MODE, &item.id);
+        self.output.emit(&item.id)?;
+        Ok(Activation::Done)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn no_usage(_mode: &str) -> HashMap<String, usize> {
+        HashMap::new()
+    }
+
+    fn thumbs_used(_mode: &str) -> HashMap<String, usize> {
+        HashMap::from([("👎".to_string(), 3)])
+    }
+
+    fn first_match(provider: &EmojiProvider, query: &str) -> Option<String> {
+        provider
+            .load()
+            .items
+            .into_iter()
+            .find(|item| item.matches(query))
+            .map(|item| item.id)
+    }
+
+    #[test]
+    fn test_characters_are_found_by_name_and_keyword() {
+        let unit = EmojiProvider::new(Output::Stdout, no_usage, |_, _| {});
+
+        assert_eq!(first_match(&unit, "arrow right").as_deref(), Some("→"));
+        assert_eq!(first_match(&unit, "thumbs").as_deref(), Some("👍"));
+        assert_eq!(first_match(&unit, "degree").as_deref(), Some("°"));
+    }
+
+    #[test]
+    fn test_used_characters_rank_first() {
+        let unit = EmojiProvider::new(Output::Stdout, thumbs_used, |_, _| {});
+
+        assert_eq!(unit.load().items[0].id, "👎");
+        assert_eq!(first_match(&unit, "thumbs").as_deref(), Some("👎"));
+    }
+}
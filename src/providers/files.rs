@@ -0,0 +1,101 @@
+//! File search through the `locate` database, offered as a row that runs the search when chosen.
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{bail, Context};
+
+use crate::provider::{Activation, Item, Listing, Provider};
+
+/// The most files a search lists
+const MAX_RESULTS: usize = 200;
+
+/// A row that searches files named like the query, then lists the files found.
+#[derive(Debug)]
+pub struct FilesProvider {
+    /// A function that opens a path or URL with its default application
+    opener: fn(&str) -> anyhow::Result<()>,
+}
+
+/// What choosing an item does.
+#[derive(Debug, Clone, PartialEq)]
+enum FilesChoice {
+    /// Search for files matching the text
+    Search(String),
+    /// Open the file at this path
+    Open(String),
+}
+
+impl FilesProvider {
+    pub fn new(opener: fn(&str) -> anyhow::Result<()>) -> Self {
+        FilesProvider { opener }
+    }
+}
+
+/// List the files found by `locate` whose names contain `query`, ignoring case.
+fn locate(query: &str) -> anyhow::Result<Listing> {
+    let output = Command::new("locate")
+        .args(["--ignore-case", "--basename", "--limit"])
+        .arg(MAX_RESULTS.to_string())
+        .arg("--")
+        .arg(query)
+        .output()
+        .context("Failed to run locate; is plocate or mlocate installed?")?;
+    // locate exits with 1 when nothing is found
+    if !output.status.success() && !output.stderr.is_empty() {
+        bail!(
+            "locate failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    let items: Vec<Item> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|path| {
+            let name = Path::new(path)
+                .file_name()
+                .map_or(path.into(), |name| name.to_string_lossy());
+            Item::new(path, name)
+                .with_subtitle(path)
+                .with_keywords([path])
+                .with_data(FilesChoice::Open(path.to_string()))
+        })
+        .collect();
+    let message = items
+        .is_empty()
+        .then(|| format!("No files named like \"{query}\""));
+    Ok(Listing {
+        items,
+        message,
+        prompt: Some("files".to_string()),
+    })
+}
+
+impl Provider for FilesProvider {
+    fn name(&self) -> &str {
+        "files"
+    }
+
+    fn load(&self) -> Listing {
+        Listing::default()
+    }
+
+    fn query(&self, query: &str) -> Vec<Item> {
+        let query = query.trim();
+        if query.is_empty() {
+            return Vec::new();
+        }
+        vec![Item::new(query, format!("Search files: {query}"))
+            .with_data(FilesChoice::Search(query.to_string()))]
+    }
+
+    fn activate(&self, item: &Item) -> anyhow::Result<Activation> {
+        match item.data::<FilesChoice>() {
+            Some(FilesChoice::Search(query)) => Ok(Activation::Replace(locate(query)?)),
+            Some(FilesChoice::Open(path)) => {
+                (self.opener)(path)?;
+                Ok(Activation::Done)
+            }
+            None => Ok(Activation::Done),
+        }
+    }
+}
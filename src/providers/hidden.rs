@@ -0,0 +1,126 @@
+//! The apps hidden with Ctrl+H, listed so they can be shown again.
+use std::path::PathBuf;
+
+use elbey_cache::load_icon;
+
+use crate::provider::{Activation, Item, Listing, Provider};
+use crate::values::IconHandle;
+
+/// An app hidden from the launcher.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HiddenApp {
+    pub appid: String,
+    pub title: String,
+    pub icon_name: Option<String>,
+    /// The desktop entry override that hides the app, or `None` if it is on elbey's blocklist
+    pub override_path: Option<PathBuf>,
+}
+
+/// Lists the hidden apps, and shows the chosen one again.
+#[derive(Debug)]
+pub struct HiddenProvider {
+    /// A function that returns the hidden apps
+    loader: fn() -> Vec<HiddenApp>,
+    /// A function that shows a hidden app again
+    unhider: fn(&HiddenApp) -> anyhow::Result<()>,
+}
+
+impl HiddenProvider {
+    pub fn new(
+        loader: fn() -> Vec<HiddenApp>,
+        unhider: fn(&HiddenApp) -> anyhow::Result<()>,
+    ) -> Self {
+        HiddenProvider { loader, unhider }
+    }
+}
+
+impl Provider for HiddenProvider {
+    fn name(&self) -> &str {
+        "hidden"
+    }
+
+    fn load(&self) -> Listing {
+        let items: Vec<Item> = (self.loader)()
+            .into_iter()
+            .map(|app| {
+                let subtitle = match &app.override_path {
+                    Some(path) => format!("NoDisplay override in {}", path.display()),
+                    None => "Blocklisted".to_string(),
+                };
+                let icon = app
+                    .icon_name
+                    .as_deref()
+                    .map_or(IconHandle::NotLoaded, load_icon);
+                Item::new(app.appid.clone(), app.title.clone())
+                    .with_subtitle(subtitle)
+                    .with_keywords([app.appid.clone()])
+                    .with_icon(icon)
+                    .with_data(app)
+            })
+            .collect();
+        let message = items.is_empty().then(|| "No apps are hidden".to_string());
+        Listing {
+            items,
+            message,
+            prompt: Some("unhide".to_string()),
+        }
+    }
+
+    fn activate(&self, item: &Item) -> anyhow::Result<Activation> {
+        let Some(app) = item.data::<HiddenApp>() else {
+            return Ok(Activation::Done);
+        };
+        (self.unhider)(app)?;
+        let mut listing = self.load();
+        listing.message = Some(format!("{} is shown again", app.title));
+        Ok(Activation::Replace(listing))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    static HIDDEN: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    #[test]
+    fn test_unhiding_removes_the_app_from_the_list() {
+        *HIDDEN.lock().unwrap() = vec!["assistant".to_string(), "bssh".to_string()];
+        let unit = HiddenProvider::new(
+            || {
+                HIDDEN
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .map(|appid| HiddenApp {
+                        appid: appid.clone(),
+                        title: appid.to_uppercase(),
+                        icon_name: None,
+                        override_path: (appid == "bssh").then(|| {
+                            PathBuf::from("/home/user/.local/share/applications/bssh.desktop")
+                        }),
+                    })
+                    .collect()
+            },
+            |app| {
+                HIDDEN.lock().unwrap().retain(|appid| *appid != app.appid);
+                Ok(())
+            },
+        );
+
+        let listing = unit.load();
+        assert_eq!(listing.items[0].subtitle.as_deref(), Some("Blocklisted"));
+        assert_eq!(
+            listing.items[1].subtitle.as_deref(),
+            Some("NoDisplay override in /home/user/.local/share/applications/bssh.desktop")
+        );
+
+        let Activation::Replace(listing) = unit.activate(&listing.items[0]).unwrap() else {
+            panic!("expected the remaining hidden apps");
+        };
+        assert_eq!(listing.items.len(), 1);
+        assert_eq!(listing.items[0].title, "BSSH");
+        assert_eq!(listing.message.as_deref(), Some("ASSISTANT is shown again"));
+    }
+}
@@ -0,0 +1,88 @@
+//! The man pages on the manual search path, opened with `man` in a terminal.
+use elbey_cache::ManPage;
+
+use crate::provider::{Activation, Item, Listing, Provider};
+use crate::terminal::Terminal;
+
+/// Lists man pages by name and section, and opens the chosen one in a terminal.
+#[derive(Debug)]
+pub struct ManProvider {
+    /// A function that returns the indexed man pages
+    loader: fn() -> Vec<ManPage>,
+    terminal: Terminal,
+    /// A function that runs a program and its arguments
+    launcher: fn(&[String]) -> anyhow::Result<()>,
+}
+
+impl ManProvider {
+    pub fn new(
+        loader: fn() -> Vec<ManPage>,
+        terminal: Terminal,
+        launcher: fn(&[String]) -> anyhow::Result<()>,
+    ) -> Self {
+        ManProvider {
+            loader,
+            terminal,
+            launcher,
+        }
+    }
+}
+
+impl Provider for ManProvider {
+    fn name(&self) -> &str {
+        "man"
+    }
+
+    fn load(&self) -> Listing {
+        (self.loader)()
+            .into_iter()
+            .map(|page| {
+                let title = format!("{} ({})", page.name, page.section);
+                let item = Item::new(title.clone(), title);
+                match &page.description {
+                    Some(description) => item.with_subtitle(description.clone()),
+                    None => item,
+                }
+                .with_data(page)
+            })
+            .collect::<Vec<_>>()
+            .into()
+    }
+
+    fn activate(&self, item: &Item) -> anyhow::Result<Activation> {
+        if let Some(page) = item.data::<ManPage>() {
+            let command = self.terminal.command(&["man", &page.section, &page.name]);
+            (self.launcher)(&command)?;
+        }
+        Ok(Activation::Done)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pages_open_in_a_terminal() {
+        let unit = ManProvider::new(
+            || {
+                vec![ManPage {
+                    name: "ls".to_string(),
+                    section: "1".to_string(),
+                    description: Some("list directory contents".to_string()),
+                }]
+            },
+            Terminal::new(Some("foot")).unwrap(),
+            |args| anyhow::bail!("{}", args.join(" ")),
+        );
+
+        let items = unit.load().items;
+        assert_eq!(items[0].title, "ls (1)");
+        assert_eq!(
+            items[0].subtitle.as_deref(),
+            Some("list directory contents")
+        );
+        let err = unit.activate(&items[0]).unwrap_err();
+        assert_eq!(err.to_string(), "foot man 1 ls");
+    }
+}
@@ -0,0 +1,173 @@
+//! The applications that can open a given file or URL, for an "Open With…" dialog.  Apps are
+//! listed when their `MimeType=` declares the target's type or one it is a kind of, or
+//! `mimeapps.list` associates them with it, with the defaults from `mimeapps.list` first.
+use elbey_cache::load_icon;
+use freedesktop_desktop_entry::DesktopEntry;
+
+use crate::mime_apps::{expand_exec, Associations, Target};
+use crate::mime_types::MimeDatabase;
+use crate::provider::{Activation, Item, Listing, Provider};
+use crate::values::IconHandle;
+
+/// Lists the apps that open a target, and opens it with the chosen one.
+#[derive(Debug)]
+pub struct OpenWithProvider {
+    target: Target,
+    database: MimeDatabase,
+    /// A function that returns the desktop entries of the installed apps
+    entries: fn() -> Vec<DesktopEntry>,
+    /// The defaults and associations chosen in `mimeapps.list`
+    associations: Associations,
+    /// A function that runs a program and its arguments
+    launcher: fn(&[String]) -> anyhow::Result<()>,
+}
+
+impl OpenWithProvider {
+    pub fn new(
+        target: Target,
+        database: MimeDatabase,
+        entries: fn() -> Vec<DesktopEntry>,
+        associations: Associations,
+        launcher: fn(&[String]) -> anyhow::Result<()>,
+    ) -> Self {
+        OpenWithProvider {
+            target,
+            database,
+            entries,
+            associations,
+            launcher,
+        }
+    }
+}
+
+impl Provider for OpenWithProvider {
+    fn name(&self) -> &str {
+        "open with"
+    }
+
+    fn load(&self) -> Listing {
+        let types = self.database.supertypes(&self.target.mime_type);
+        let defaults: Vec<String> = types
+            .iter()
+            .flat_map(|mime_type| self.associations.defaults(mime_type))
+            .collect();
+
+        // Sort by default app preference, then by how specific the supported type is
+        let mut apps: Vec<(usize, usize, String, DesktopEntry)> = (self.entries)()
+            .into_iter()
+            .filter(|entry| entry.exec().is_some())
+            .filter_map(|entry| {
+                // Apps support a type by declaring it or by an added association, unless the
+                // association was removed
+                let declared = entry.mime_type().unwrap_or_default();
+                let specificity = types.iter().position(|mime_type| {
+                    let supported = declared
+                        .iter()
+                        .any(|declared| self.database.unalias(declared) == mime_type)
+                        || self.associations.added(mime_type).contains(&entry.appid);
+                    supported && !self.associations.removed(mime_type, &entry.appid)
+                })?;
+                let default = defaults
+                    .iter()
+                    .position(|id| *id == entry.appid)
+                    .unwrap_or(usize::MAX);
+                let title = entry.desktop_entry("Name")?.to_string();
+                Some((default, specificity, title, entry))
+            })
+            .collect();
+        apps.sort_by(|a, b| (a.0, a.1, &a.2).cmp(&(b.0, b.1, &b.2)));
+
+        let items = apps
+            .into_iter()
+            .map(|(default, _, title, entry)| {
+                let icon = entry.icon().map_or(IconHandle::NotLoaded, load_icon);
+                let item = Item::new(entry.appid.clone(), title)
+                    .with_icon(icon)
+                    .with_data(entry.exec().unwrap_or_default().to_string());
+                if default == usize::MAX {
+                    item
+                } else {
+                    item.with_subtitle("Default application")
+                }
+            })
+            .collect();
+        let target = self
+            .target
+            .path
+            .as_ref()
+            .map_or(self.target.uri.clone(), |path| path.display().to_string());
+        Listing {
+            items,
+            message: Some(format!("{target} ({})", self.target.mime_type)),
+            prompt: Some("open with".to_string()),
+        }
+    }
+
+    fn activate(&self, item: &Item) -> anyhow::Result<Activation> {
+        if let Some(exec) = item.data::<String>() {
+            let path = self
+                .target
+                .path
+                .as_ref()
+                .map(|path| path.to_string_lossy().into_owned());
+            (self.launcher)(&expand_exec(exec, path.as_deref(), &self.target.uri)?)?;
+        }
+        Ok(Activation::Done)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn entry(appid: &str, name: &str, exec: &str, mime_types: &str) -> DesktopEntry {
+        let contents = format!(
+            "[Desktop Entry]\nType=Application\nName={name}\nExec={exec}\nMimeType={mime_types}\n"
+        );
+        let mut entry =
+            DesktopEntry::from_str(format!("/{appid}.desktop"), &contents, None::<&[&str]>)
+                .unwrap();
+        entry.appid = appid.to_string();
+        entry
+    }
+
+    #[test]
+    fn test_apps_supporting_the_type_are_listed_defaults_first() {
+        let target = Target {
+            path: Some(PathBuf::from("/home/user/notes.md")),
+            uri: "file:///home/user/notes.md".to_string(),
+            mime_type: "text/markdown".to_string(),
+        };
+        let database = MimeDatabase::parse("", "", "text/x-markdown text/markdown\n");
+        let unit = OpenWithProvider::new(
+            target,
+            database,
+            || {
+                vec![
+                    entry("gimp", "GIMP", "gimp %U", "image/png;"),
+                    entry("gedit", "Text Editor", "gedit %U", "text/plain;"),
+                    entry("typora", "Typora", "typora %f", "text/x-markdown;"),
+                    entry("vim", "Vim", "vim %F", "text/plain;"),
+                    entry("nano", "Nano", "nano %F", "text/plain;"),
+                    entry("marktext", "Mark Text", "marktext %F", ""),
+                ]
+            },
+            Associations::parse(&[
+                "[Default Applications]\ntext/plain=vim.desktop\n[Added Associations]\ntext/markdown=marktext.desktop\n[Removed Associations]\ntext/plain=nano.desktop\n",
+            ]),
+            |args| anyhow::bail!("{}", args.join(" ")),
+        );
+
+        let listing = unit.load();
+        let titles: Vec<&str> = listing.items.iter().map(|i| i.title.as_str()).collect();
+        assert_eq!(titles, vec!["Vim", "Mark Text", "Typora", "Text Editor"]);
+        assert_eq!(
+            listing.items[0].subtitle.as_deref(),
+            Some("Default application")
+        );
+
+        let err = unit.activate(&listing.items[2]).unwrap_err();
+        assert_eq!(err.to_string(), "typora /home/user/notes.md");
+    }
+}
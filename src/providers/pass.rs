@@ -0,0 +1,211 @@
+//! Entries of the [pass](https://www.passwordstore.org/) password store, listed from the `.gpg`
+//! files under the store directory.  Decryption is left to the configured command.
+use std::collections::HashSet;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+use anyhow::ensure;
+
+use crate::provider::{Activation, Item, Listing, Provider};
+
+/// Run for the chosen entry by default; `%s` is replaced by the entry name
+pub const DEFAULT_PASS_COMMAND: &str = "pass show -c %s";
+/// Run for the chosen entry with Ctrl+Enter by default
+pub const DEFAULT_PASS_OTP_COMMAND: &str = "pass otp -c %s";
+
+/// Lists password store entries and runs a command with the chosen one.
+#[derive(Debug)]
+pub struct PassProvider {
+    store_dir: PathBuf,
+    /// The command run for an entry, with `%s` for the entry name
+    command: Vec<String>,
+    /// The command run for an entry with Ctrl+Enter
+    otp_command: Vec<String>,
+    /// A function that runs a program and its arguments
+    launcher: fn(&[String]) -> anyhow::Result<()>,
+}
+
+/// Split a command line, requiring a `%s` argument for the entry name.
+fn parse_command(command: &str) -> anyhow::Result<Vec<String>> {
+    let args = shell_words::split(command)?;
+    ensure!(
+        args.iter().any(|arg| arg.contains("%s")),
+        "`{command}` has no %s for the entry name"
+    );
+    Ok(args)
+}
+
+impl PassProvider {
+    pub fn new(
+        store_dir: PathBuf,
+        command: &str,
+        otp_command: &str,
+        launcher: fn(&[String]) -> anyhow::Result<()>,
+    ) -> anyhow::Result<Self> {
+        Ok(PassProvider {
+            store_dir,
+            command: parse_command(command)?,
+            otp_command: parse_command(otp_command)?,
+            launcher,
+        })
+    }
+
+    fn run(&self, command: &[String], item: &Item) -> anyhow::Result<Activation> {
+        let args: Vec<String> = command
+            .iter()
+            .map(|arg| arg.replace("%s", &item.id))
+            .collect();
+        (self.launcher)(&args)?;
+        Ok(Activation::Done)
+    }
+}
+
+/// The store directory: `$PASSWORD_STORE_DIR`, or `~/.password-store`.
+pub fn default_store_dir() -> Option<PathBuf> {
+    std::env::var_os("PASSWORD_STORE_DIR")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".password-store")))
+}
+
+/// Collect the names of the `.gpg` files under `dir`, relative to `store_dir` and without the
+/// extension, skipping hidden files and directories such as `.git`.  `visited` holds the
+/// `(device, inode)` of each directory walked, so symlinks looping back are not followed again.
+fn walk_entries(
+    store_dir: &Path,
+    dir: &Path,
+    visited: &mut HashSet<(u64, u64)>,
+    entries: &mut Vec<String>,
+) {
+    let Ok(metadata) = std::fs::metadata(dir) else {
+        return;
+    };
+    if !visited.insert((metadata.dev(), metadata.ino())) {
+        return;
+    }
+    let Ok(read_dir) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in read_dir.filter_map(Result::ok) {
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        let path = entry.path();
+        // Follow symlinks, as stores may link shared folders in
+        let Ok(metadata) = std::fs::metadata(&path) else {
+            continue;
+        };
+        if metadata.is_dir() {
+            walk_entries(store_dir, &path, visited, entries);
+        } else if path.extension().is_some_and(|extension| extension == "gpg") {
+            if let Ok(relative) = path.with_extension("").strip_prefix(store_dir) {
+                entries.push(relative.to_string_lossy().into_owned());
+            }
+        }
+    }
+}
+
+impl Provider for PassProvider {
+    fn name(&self) -> &str {
+        "pass"
+    }
+
+    fn load(&self) -> Listing {
+        if !self.store_dir.is_dir() {
+            return Listing {
+                message: Some(format!("No password store at {}", self.store_dir.display())),
+                ..Listing::default()
+            };
+        }
+        let mut entries = Vec::new();
+        walk_entries(
+            &self.store_dir,
+            &self.store_dir,
+            &mut HashSet::new(),
+            &mut entries,
+        );
+        entries.sort_unstable();
+        entries
+            .into_iter()
+            .map(|entry| Item::new(entry.clone(), entry))
+            .collect::<Vec<_>>()
+            .into()
+    }
+
+    fn activate(&self, item: &Item) -> anyhow::Result<Activation> {
+        self.run(&self.command, item)
+    }
+
+    fn activate_alternate(&self, item: &Item) -> anyhow::Result<Activation> {
+        self.run(&self.otp_command, item)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entries_are_listed_and_launched() {
+        let store = std::env::temp_dir().join(format!("elbey-pass-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&store);
+        for file in [
+            "email/work.gpg",
+            "email/home.gpg",
+            "bank.gpg",
+            "notes.txt",
+            ".gpg-id",
+            ".git/objects/ab.gpg",
+        ] {
+            let path = store.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+
+        let unit = PassProvider::new(
+            store.clone(),
+            DEFAULT_PASS_COMMAND,
+            DEFAULT_PASS_OTP_COMMAND,
+            |args| anyhow::bail!("{}", args.join(" ")),
+        )
+        .unwrap();
+        let items = unit.load().items;
+        let ids: Vec<&str> = items.iter().map(|item| item.id.as_str()).collect();
+        assert_eq!(ids, vec!["bank", "email/home", "email/work"]);
+
+        let ran = unit.activate(&items[2]).unwrap_err().to_string();
+        assert_eq!(ran, "pass show -c email/work");
+        let ran = unit.activate_alternate(&items[2]).unwrap_err().to_string();
+        assert_eq!(ran, "pass otp -c email/work");
+
+        assert!(
+            PassProvider::new(store.clone(), "pass show -c", "", |args| anyhow::bail!(
+                "{}",
+                args.join(" ")
+            ))
+            .is_err()
+        );
+        let _ = std::fs::remove_dir_all(store);
+    }
+
+    #[test]
+    fn test_symlink_loops_are_walked_once() {
+        let store = std::env::temp_dir().join(format!("elbey-pass-loop-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&store);
+        std::fs::create_dir_all(store.join("email")).unwrap();
+        std::fs::write(store.join("email/work.gpg"), "").unwrap();
+        std::os::unix::fs::symlink("..", store.join("email/shared")).unwrap();
+
+        let unit = PassProvider::new(
+            store.clone(),
+            DEFAULT_PASS_COMMAND,
+            DEFAULT_PASS_OTP_COMMAND,
+            |args| anyhow::bail!("{}", args.join(" ")),
+        )
+        .unwrap();
+        let items = unit.load().items;
+        let ids: Vec<&str> = items.iter().map(|item| item.id.as_str()).collect();
+        assert_eq!(ids, vec!["email/work"]);
+        let _ = std::fs::remove_dir_all(store);
+    }
+}
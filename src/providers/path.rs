@@ -0,0 +1,231 @@
+//! Paths and URLs typed into the query: a query starting with `/` or `~` lists the entries of the
+//! typed directory that start with the last path component, and Tab completes the selected one.
+//! Directories open in the default file manager, and files and URLs with their default
+//! application.
+use std::path::PathBuf;
+
+use crate::provider::{Activation, Item, Listing, Provider};
+
+/// The most directory entries listed
+const MAX_ENTRIES: usize = 500;
+
+/// Expand a leading `~` to the home directory.
+pub fn expand_tilde(path: &str) -> PathBuf {
+    match (path.strip_prefix('~'), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => {
+            PathBuf::from(format!("{}{rest}", home.to_string_lossy()))
+        }
+        _ => PathBuf::from(path),
+    }
+}
+
+fn is_path_query(query: &str) -> bool {
+    query.starts_with('/') || query.starts_with('~')
+}
+
+/// Whether `query` is a URL such as `https://example.com` or `sftp://host/dir`.
+fn is_url(query: &str) -> bool {
+    query.split_once("://").is_some_and(|(scheme, rest)| {
+        scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "+.-".contains(c))
+            && !rest.is_empty()
+            && !rest.contains(char::is_whitespace)
+    })
+}
+
+/// A listed file, directory or URL.
+#[derive(Debug, Clone, PartialEq)]
+struct PathEntry {
+    /// The path or URL to open
+    target: String,
+    /// The query that completes to this entry, as typed, e.g. `~/Documents/`
+    completion: String,
+}
+
+/// Lists the entries of a typed directory and opens the chosen one.
+#[derive(Debug)]
+pub struct PathProvider {
+    /// A function that opens a path or URL with its default application
+    opener: fn(&str) -> anyhow::Result<()>,
+}
+
+impl PathProvider {
+    pub fn new(opener: fn(&str) -> anyhow::Result<()>) -> Self {
+        PathProvider { opener }
+    }
+}
+
+fn entry_item(title: String, path: PathBuf, completion: String) -> Item {
+    let target = path.to_string_lossy().into_owned();
+    Item::new(target.clone(), title)
+        .with_subtitle(target.clone())
+        .with_data(PathEntry { target, completion })
+}
+
+impl Provider for PathProvider {
+    fn name(&self) -> &str {
+        "path"
+    }
+
+    fn load(&self) -> Listing {
+        Listing::default()
+    }
+
+    fn owns_query(&self, query: &str) -> bool {
+        let query = query.trim_start();
+        is_path_query(query) || is_url(query.trim_end())
+    }
+
+    fn query(&self, query: &str) -> Vec<Item> {
+        let query = query.trim_start();
+        if is_url(query.trim_end()) {
+            let url = query.trim_end().to_string();
+            return vec![
+                Item::new(url.clone(), format!("Open: {url}")).with_data(PathEntry {
+                    target: url.clone(),
+                    completion: url,
+                }),
+            ];
+        }
+        if !is_path_query(query) {
+            return Vec::new();
+        }
+        // A bare `~` lists the home directory
+        let query = if query == "~" { "~/" } else { query };
+        let (typed_dir, component) = match query.rfind('/') {
+            Some(slash) => query.split_at(slash + 1),
+            None => return Vec::new(),
+        };
+        let dir = expand_tilde(typed_dir);
+        let Ok(read_dir) = std::fs::read_dir(&dir) else {
+            return Vec::new();
+        };
+
+        let component_lower = component.to_lowercase();
+        let show_hidden = component.starts_with('.');
+        let mut names: Vec<String> = read_dir
+            .filter_map(Result::ok)
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .filter(|name| {
+                (show_hidden || !name.starts_with('.'))
+                    && name.to_lowercase().starts_with(&component_lower)
+            })
+            .collect();
+        // Sort all the matches before truncating, so no early entries go missing
+        names.sort_by_cached_key(|name| name.to_lowercase());
+        names.truncate(MAX_ENTRIES);
+        let entries = names.into_iter().map(|name| {
+            // Follow symlinks, so a link to a directory completes like one
+            let is_dir = dir.join(&name).is_dir();
+            (name, is_dir)
+        });
+
+        let mut items = Vec::new();
+        if component.is_empty() {
+            items.push(entry_item(
+                format!("Open: {typed_dir}"),
+                dir.clone(),
+                typed_dir.to_string(),
+            ));
+        }
+        items.extend(entries.map(|(name, is_dir)| {
+            let suffix = if is_dir { "/" } else { "" };
+            entry_item(
+                format!("{name}{suffix}"),
+                dir.join(&name),
+                format!("{typed_dir}{name}{suffix}"),
+            )
+        }));
+        items
+    }
+
+    fn complete(&self, item: &Item) -> Option<String> {
+        item.data::<PathEntry>()
+            .map(|entry| entry.completion.clone())
+    }
+
+    fn activate(&self, item: &Item) -> anyhow::Result<Activation> {
+        if let Some(entry) = item.data::<PathEntry>() {
+            (self.opener)(&entry.target)?;
+        }
+        Ok(Activation::Done)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn titles(provider: &PathProvider, query: &str) -> Vec<String> {
+        provider
+            .query(query)
+            .into_iter()
+            .map(|item| item.title)
+            .collect()
+    }
+
+    #[test]
+    fn test_directory_entries_complete_the_last_component() {
+        let dir = std::env::temp_dir().join(format!("elbey-path-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("Documents")).unwrap();
+        std::fs::create_dir_all(dir.join(".config")).unwrap();
+        std::fs::write(dir.join("dotfile-notes.txt"), "").unwrap();
+        std::fs::write(dir.join(".profile"), "").unwrap();
+        let base = format!("{}/", dir.display());
+        let unit = PathProvider::new(|_| Ok(()));
+
+        assert!(unit.owns_query("/etc") && unit.owns_query("~/x") && !unit.owns_query("firefox"));
+        assert!(unit.owns_query("https://example.com/a?b") && !unit.owns_query("a://"));
+        assert_eq!(
+            titles(&unit, "sftp://host/dir "),
+            vec!["Open: sftp://host/dir"]
+        );
+        assert_eq!(
+            titles(&unit, &base),
+            vec![
+                format!("Open: {base}"),
+                "Documents/".to_string(),
+                "dotfile-notes.txt".to_string()
+            ]
+        );
+        assert_eq!(
+            titles(&unit, &format!("{base}do")),
+            vec!["Documents/", "dotfile-notes.txt"]
+        );
+        assert_eq!(
+            titles(&unit, &format!("{base}.")),
+            vec![".config/", ".profile"]
+        );
+
+        let items = unit.query(&format!("{base}doc"));
+        assert_eq!(unit.complete(&items[0]), Some(format!("{base}Documents/")));
+        assert_eq!(items[0].id, dir.join("Documents").to_string_lossy());
+        assert!(unit.query("/no/such/elbey/dir/").is_empty());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_large_directories_keep_the_first_entries() {
+        let dir = std::env::temp_dir().join(format!("elbey-path-many-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        for index in (0..MAX_ENTRIES + 100).rev() {
+            std::fs::write(dir.join(format!("file-{index:04}")), "").unwrap();
+        }
+        let unit = PathProvider::new(|_| Ok(()));
+
+        let titles = titles(&unit, &format!("{}/file", dir.display()));
+        assert_eq!(titles.len(), MAX_ENTRIES);
+        assert_eq!(titles[0], "file-0000");
+        assert_eq!(
+            titles[MAX_ENTRIES - 1],
+            format!("file-{:04}", MAX_ENTRIES - 1)
+        );
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
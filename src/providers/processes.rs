@@ -0,0 +1,289 @@
+//! The user's running processes, read from `/proc`, to end with a signal.
+use std::collections::HashMap;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{bail, Context};
+use elbey_cache::{load_icon, AppDescriptor};
+
+use crate::provider::{Activation, Item, Listing, Provider};
+use crate::values::IconHandle;
+
+/// A running process.
+#[derive(Debug, Clone, PartialEq)]
+struct Process {
+    pid: u32,
+    name: String,
+    cmdline: String,
+    /// Resident memory in KiB
+    rss_kib: u64,
+    /// The file name of the executable
+    executable: Option<String>,
+}
+
+/// Lists the user's processes and signals the chosen one: SIGTERM, or SIGKILL with Ctrl+Enter.
+#[derive(Debug)]
+pub struct ProcessesProvider {
+    /// The proc filesystem to read
+    proc_dir: PathBuf,
+    /// Only processes of this user are listed
+    uid: u32,
+    /// A function that returns the desktop apps, to find process icons
+    apps: fn() -> Vec<AppDescriptor>,
+    /// A function that sends a signal, named like `TERM`, to a process
+    signal: fn(u32, &str) -> anyhow::Result<()>,
+}
+
+impl ProcessesProvider {
+    pub fn new(
+        proc_dir: PathBuf,
+        uid: u32,
+        apps: fn() -> Vec<AppDescriptor>,
+        signal: fn(u32, &str) -> anyhow::Result<()>,
+    ) -> Self {
+        ProcessesProvider {
+            proc_dir,
+            uid,
+            apps,
+            signal,
+        }
+    }
+
+    fn send(&self, item: &Item, signal: &str) -> anyhow::Result<Activation> {
+        let Some(process) = item.data::<Process>() else {
+            return Ok(Activation::Done);
+        };
+        (self.signal)(process.pid, signal)?;
+        let mut listing = self.load();
+        listing.message = Some(format!(
+            "Sent SIG{signal} to {} ({})",
+            process.name, process.pid
+        ));
+        Ok(Activation::Replace(listing))
+    }
+}
+
+/// The user id owning the current process.
+pub fn current_uid() -> anyhow::Result<u32> {
+    Ok(std::fs::metadata("/proc/self")?.uid())
+}
+
+/// Send `signal`, named like `TERM`, to the process `pid` with `kill`.
+pub fn send_signal(pid: u32, signal: &str) -> anyhow::Result<()> {
+    let output = Command::new("kill")
+        .args(["-s", signal, &pid.to_string()])
+        .output()
+        .context("Failed to run kill")?;
+    if !output.status.success() {
+        bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(())
+}
+
+/// Read the process whose directory is `dir`, or `None` for kernel threads and processes that
+/// ended while being read.
+fn read_process(dir: &Path, pid: u32) -> Option<Process> {
+    let cmdline = std::fs::read(dir.join("cmdline")).ok()?;
+    if cmdline.is_empty() {
+        return None;
+    }
+    let cmdline = String::from_utf8_lossy(&cmdline)
+        .split('\0')
+        .filter(|arg| !arg.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let name = std::fs::read_to_string(dir.join("comm"))
+        .map(|comm| comm.trim_end().to_string())
+        .ok()?;
+    let rss_kib = std::fs::read_to_string(dir.join("status"))
+        .ok()
+        .and_then(|status| {
+            let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+            line.split_whitespace().nth(1)?.parse().ok()
+        })
+        .unwrap_or(0);
+    let executable = std::fs::read_link(dir.join("exe"))
+        .ok()
+        .and_then(|exe| Some(exe.file_name()?.to_string_lossy().into_owned()))
+        // A replaced executable is shown as `name (deleted)`
+        .map(|exe| exe.trim_end_matches(" (deleted)").to_string());
+    Some(Process {
+        pid,
+        name,
+        cmdline,
+        rss_kib,
+        executable,
+    })
+}
+
+/// Format a memory size given in KiB, e.g. `12.3 MiB`.
+fn format_memory(kib: u64) -> String {
+    if kib >= 1024 * 1024 {
+        format!("{:.1} GiB", kib as f64 / (1024.0 * 1024.0))
+    } else if kib >= 1024 {
+        format!("{:.1} MiB", kib as f64 / 1024.0)
+    } else {
+        format!("{kib} KiB")
+    }
+}
+
+/// The file name of the program an app's `Exec` line runs, skipping `env` and its variables.
+fn exec_program(exec: &str) -> Option<String> {
+    let args = shell_words::split(exec).ok()?;
+    let program = args
+        .iter()
+        .find(|arg| *arg != "env" && !arg.contains('='))?;
+    Some(
+        Path::new(program)
+            .file_name()?
+            .to_string_lossy()
+            .into_owned(),
+    )
+}
+
+impl Provider for ProcessesProvider {
+    fn name(&self) -> &str {
+        "processes"
+    }
+
+    fn load(&self) -> Listing {
+        let Ok(read_dir) = std::fs::read_dir(&self.proc_dir) else {
+            return Listing {
+                message: Some(format!("Failed to read {}", self.proc_dir.display())),
+                ..Listing::default()
+            };
+        };
+        let own_pid = std::process::id();
+        let mut processes: Vec<Process> = read_dir
+            .filter_map(Result::ok)
+            .filter_map(|entry| {
+                let pid: u32 = entry.file_name().to_str()?.parse().ok()?;
+                let owner = entry.metadata().ok()?.uid();
+                (pid != own_pid && owner == self.uid)
+                    .then(|| read_process(&entry.path(), pid))
+                    .flatten()
+            })
+            .collect();
+        // The heaviest processes first
+        processes.sort_by(|a, b| b.rss_kib.cmp(&a.rss_kib).then(a.pid.cmp(&b.pid)));
+
+        let apps: HashMap<String, AppDescriptor> = (self.apps)()
+            .into_iter()
+            .filter_map(|app| Some((exec_program(app.exec.as_deref()?)?, app)))
+            .collect();
+        let mut icons: HashMap<String, IconHandle> = HashMap::new();
+
+        processes
+            .into_iter()
+            .map(|process| {
+                let app = process
+                    .executable
+                    .as_ref()
+                    .and_then(|exe| apps.get(exe))
+                    .or_else(|| apps.get(&process.name));
+                let icon = app
+                    .map(|app| {
+                        icons
+                            .entry(app.appid.clone())
+                            .or_insert_with(|| match (&app.icon_handle, &app.icon_name) {
+                                (IconHandle::NotLoaded, Some(icon)) => load_icon(icon),
+                                (handle, _) => handle.clone(),
+                            })
+                            .clone()
+                    })
+                    .unwrap_or(IconHandle::NotLoaded);
+                let subtitle = format!(
+                    "{} · {} · {}",
+                    process.pid,
+                    format_memory(process.rss_kib),
+                    process.cmdline
+                );
+                Item::new(process.pid.to_string(), process.name.clone())
+                    .with_subtitle(subtitle)
+                    .with_icon(icon)
+                    .with_keywords([process.pid.to_string(), process.cmdline.clone()])
+                    .with_data(process)
+            })
+            .collect::<Vec<_>>()
+            .into()
+    }
+
+    fn activate(&self, item: &Item) -> anyhow::Result<Activation> {
+        self.send(item, "TERM")
+    }
+
+    fn activate_alternate(&self, item: &Item) -> anyhow::Result<Activation> {
+        self.send(item, "KILL")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_process(proc_dir: &Path, pid: u32, comm: &str, cmdline: &str, rss_kib: u64) {
+        let dir = proc_dir.join(pid.to_string());
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("comm"), format!("{comm}\n")).unwrap();
+        std::fs::write(dir.join("cmdline"), cmdline.replace(' ', "\0")).unwrap();
+        std::fs::write(
+            dir.join("status"),
+            format!("Name:\t{comm}\nVmRSS:\t  {rss_kib} kB\n"),
+        )
+        .unwrap();
+    }
+
+    fn test_apps() -> Vec<AppDescriptor> {
+        vec![AppDescriptor {
+            appid: "org.mozilla.firefox".to_string(),
+            title: "Firefox".to_string(),
+            lower_title: "firefox".to_string(),
+            exec: Some("env MOZ_ENABLE_WAYLAND=1 /usr/lib/firefox/firefox %u".to_string()),
+            exec_count: 0,
+            icon_name: None,
+            icon_path: None,
+            icon_handle: IconHandle::NotLoaded,
+        }]
+    }
+
+    #[test]
+    fn test_processes_are_listed_and_signalled() {
+        let proc_dir = std::env::temp_dir().join(format!("elbey-proc-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&proc_dir);
+        write_process(&proc_dir, 200, "bash", "/bin/bash", 4000);
+        write_process(
+            &proc_dir,
+            100,
+            "firefox",
+            "/usr/lib/firefox/firefox -P work",
+            2_500_000,
+        );
+        write_process(&proc_dir, 2, "kthreadd", "", 0);
+        std::fs::create_dir_all(proc_dir.join("self")).unwrap();
+        let uid = std::fs::metadata(&proc_dir).unwrap().uid();
+
+        let unit = ProcessesProvider::new(proc_dir.clone(), uid, test_apps, |pid, signal| {
+            bail!("kill -s {signal} {pid}")
+        });
+        let items = unit.load().items;
+        let titles: Vec<&str> = items.iter().map(|item| item.title.as_str()).collect();
+        assert_eq!(titles, vec!["firefox", "bash"]);
+        assert_eq!(
+            items[0].subtitle.as_deref(),
+            Some("100 · 2.4 GiB · /usr/lib/firefox/firefox -P work")
+        );
+        assert!(items[0].matches("-p work"));
+        assert_eq!(
+            exec_program(test_apps()[0].exec.as_deref().unwrap()).as_deref(),
+            Some("firefox")
+        );
+
+        let sent = unit.activate(&items[1]).unwrap_err().to_string();
+        assert_eq!(sent, "kill -s TERM 200");
+        let sent = unit.activate_alternate(&items[0]).unwrap_err().to_string();
+        assert_eq!(sent, "kill -s KILL 100");
+
+        let _ = std::fs::remove_dir_all(proc_dir);
+    }
+}
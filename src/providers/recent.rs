@@ -0,0 +1,231 @@
+//! Recently used files, as recorded by GTK applications in `~/.local/share/recently-used.xbel`.
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use elbey_cache::load_icon;
+
+use crate::mime_apps::{expand_exec, percent_decode};
+use crate::provider::{Activation, Item, Listing, Provider};
+use crate::values::IconHandle;
+
+const BOOKMARK_NS: &str = "http://www.freedesktop.org/standards/desktop-bookmarks";
+const MIME_NS: &str = "http://www.freedesktop.org/standards/shared-mime-info";
+
+/// A recently used file and how to open it.
+#[derive(Debug, Clone, PartialEq)]
+struct RecentFile {
+    uri: String,
+    path: PathBuf,
+    mime_type: Option<String>,
+    /// The command line of the application that last used the file, with field codes
+    exec: Option<String>,
+    /// When the file was last used, as an ISO 8601 timestamp
+    visited: String,
+}
+
+/// Lists recently used files, most recent first, and opens the chosen one.
+#[derive(Debug)]
+pub struct RecentProvider {
+    /// The `recently-used.xbel` file to read
+    path: PathBuf,
+    /// A function that runs a program and its arguments
+    launcher: fn(&[String]) -> anyhow::Result<()>,
+    /// A function that opens a path with its default application
+    opener: fn(&str) -> anyhow::Result<()>,
+}
+
+impl RecentProvider {
+    pub fn new(
+        path: PathBuf,
+        launcher: fn(&[String]) -> anyhow::Result<()>,
+        opener: fn(&str) -> anyhow::Result<()>,
+    ) -> Self {
+        RecentProvider {
+            path,
+            launcher,
+            opener,
+        }
+    }
+}
+
+/// The default location of the recently used files list.
+pub fn default_path() -> Option<PathBuf> {
+    let data_home = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".local/share")))?;
+    Some(data_home.join("recently-used.xbel"))
+}
+
+/// Parse the bookmarks of local files in an XBEL document, most recently used first.
+fn parse_recent_files(xml: &str) -> anyhow::Result<Vec<RecentFile>> {
+    let document = roxmltree::Document::parse(xml)?;
+    let mut files: Vec<RecentFile> = document
+        .root_element()
+        .children()
+        .filter(|node| node.has_tag_name("bookmark"))
+        .filter_map(|bookmark| {
+            let uri = bookmark.attribute("href")?;
+            let path = PathBuf::from(percent_decode(uri.strip_prefix("file://")?));
+            let mime_type = bookmark
+                .descendants()
+                .find(|node| node.has_tag_name((MIME_NS, "mime-type")))
+                .and_then(|node| node.attribute("type"))
+                .map(str::to_string);
+            // The application that used the file last
+            let exec = bookmark
+                .descendants()
+                .filter(|node| node.has_tag_name((BOOKMARK_NS, "application")))
+                .max_by_key(|node| node.attribute("modified").unwrap_or_default())
+                .and_then(|node| node.attribute("exec"))
+                .map(|exec| exec.trim_matches('\'').to_string());
+            let visited = bookmark
+                .attribute("visited")
+                .or(bookmark.attribute("modified"))
+                .unwrap_or_default()
+                .to_string();
+            Some(RecentFile {
+                uri: uri.to_string(),
+                path,
+                mime_type,
+                exec,
+                visited,
+            })
+        })
+        .collect();
+    files.sort_by(|a, b| b.visited.cmp(&a.visited));
+    Ok(files)
+}
+
+/// The command line of the application that last used `file`, if one was recorded.
+fn open_command(file: &RecentFile) -> Option<anyhow::Result<Vec<String>>> {
+    let exec = file.exec.as_ref()?;
+    Some(expand_exec(
+        exec,
+        Some(&file.path.to_string_lossy()),
+        &file.uri,
+    ))
+}
+
+/// Load the themed icon for a MIME type, e.g. `application-pdf`, falling back to the generic
+/// icon of its media type.
+fn mime_icon(mime_type: &str) -> IconHandle {
+    let icon = load_icon(&mime_type.replace('/', "-"));
+    if icon != IconHandle::NotLoaded {
+        return icon;
+    }
+    let media = mime_type.split('/').next().unwrap_or(mime_type);
+    load_icon(&format!("{media}-x-generic"))
+}
+
+impl Provider for RecentProvider {
+    fn name(&self) -> &str {
+        "recent"
+    }
+
+    fn load(&self) -> Listing {
+        let files = std::fs::read_to_string(&self.path)
+            .with_context(|| format!("Failed to read {}", self.path.display()))
+            .and_then(|xml| parse_recent_files(&xml));
+        let files = match files {
+            Ok(files) => files,
+            Err(err) => {
+                return Listing {
+                    message: Some(format!("{err:#}")),
+                    ..Listing::default()
+                }
+            }
+        };
+
+        let mut icons = HashMap::new();
+        files
+            .into_iter()
+            .filter(|file| file.path.exists())
+            .map(|file| {
+                let name = file
+                    .path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| file.path.to_string_lossy().into_owned());
+                let path = file.path.to_string_lossy().into_owned();
+                let icon = file
+                    .mime_type
+                    .as_ref()
+                    .map(|mime_type| {
+                        icons
+                            .entry(mime_type.clone())
+                            .or_insert_with(|| mime_icon(mime_type))
+                            .clone()
+                    })
+                    .unwrap_or(IconHandle::NotLoaded);
+                Item::new(file.uri.clone(), name)
+                    .with_subtitle(path.clone())
+                    .with_icon(icon)
+                    .with_keywords([path])
+                    .with_data(file)
+            })
+            .collect::<Vec<_>>()
+            .into()
+    }
+
+    fn activate(&self, item: &Item) -> anyhow::Result<Activation> {
+        if let Some(file) = item.data::<RecentFile>() {
+            match open_command(file) {
+                Some(command) => (self.launcher)(&command?)?,
+                None => (self.opener)(&file.path.to_string_lossy())?,
+            }
+        }
+        Ok(Activation::Done)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const XBEL: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<xbel version="1.0"
+      xmlns:bookmark="http://www.freedesktop.org/standards/desktop-bookmarks"
+      xmlns:mime="http://www.freedesktop.org/standards/shared-mime-info">
+  <bookmark href="file:///home/user/Documents/Annual%20Report.pdf" added="2024-01-02T10:00:00Z" modified="2024-01-02T10:00:00Z" visited="2024-01-02T10:00:00Z">
+    <info>
+      <metadata owner="http://freedesktop.org">
+        <mime:mime-type type="application/pdf"/>
+        <bookmark:applications>
+          <bookmark:application name="Evince" exec="&apos;evince %u&apos;" modified="2024-01-01T10:00:00Z" count="1"/>
+          <bookmark:application name="Okular" exec="&apos;okular %f&apos;" modified="2024-01-02T10:00:00Z" count="1"/>
+        </bookmark:applications>
+      </metadata>
+    </info>
+  </bookmark>
+  <bookmark href="file:///home/user/notes.txt" added="2024-01-03T10:00:00Z" modified="2024-01-03T10:00:00Z" visited="2024-01-03T10:00:00Z"/>
+  <bookmark href="https://example.com/" added="2024-01-04T10:00:00Z" modified="2024-01-04T10:00:00Z" visited="2024-01-04T10:00:00Z"/>
+</xbel>"#;
+
+    #[test]
+    fn test_parse_recent_files() {
+        let files = parse_recent_files(XBEL).unwrap();
+
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].path, PathBuf::from("/home/user/notes.txt"));
+        assert_eq!(files[0].exec, None);
+        assert_eq!(
+            files[1].path,
+            PathBuf::from("/home/user/Documents/Annual Report.pdf")
+        );
+        assert_eq!(files[1].mime_type.as_deref(), Some("application/pdf"));
+        assert_eq!(files[1].exec.as_deref(), Some("okular %f"));
+    }
+
+    #[test]
+    fn test_open_command_substitutes_field_codes() {
+        let files = parse_recent_files(XBEL).unwrap();
+
+        assert_eq!(
+            open_command(&files[1]).unwrap().unwrap(),
+            vec!["okular", "/home/user/Documents/Annual Report.pdf"]
+        );
+        assert!(open_command(&files[0]).is_none());
+    }
+}
@@ -0,0 +1,75 @@
+//! Commands found in `$PATH`, like `dmenu_run` or rofi's run mode.
+use elbey_cache::AppDescriptor;
+
+use crate::provider::{Activation, Item, Listing, Provider};
+use crate::providers::apps::app_item;
+use crate::values::IconHandle;
+
+/// Lists executables and runs the selected or typed command.
+#[derive(Debug)]
+pub struct RunProvider {
+    /// A function that returns the executables to list
+    loader: fn() -> Vec<AppDescriptor>,
+    /// A function that runs a command
+    launcher: fn(&AppDescriptor) -> anyhow::Result<()>,
+}
+
+impl RunProvider {
+    pub fn new(
+        loader: fn() -> Vec<AppDescriptor>,
+        launcher: fn(&AppDescriptor) -> anyhow::Result<()>,
+    ) -> Self {
+        RunProvider { loader, launcher }
+    }
+}
+
+/// Describe a typed command line, ranked under the name of the program it runs.
+fn typed_command(command: &str) -> Option<AppDescriptor> {
+    let name = command.split_whitespace().next()?;
+    Some(AppDescriptor {
+        appid: name.to_string(),
+        title: command.to_string(),
+        lower_title: command.to_lowercase(),
+        exec: Some(command.to_string()),
+        exec_count: 0,
+        icon_name: None,
+        icon_path: None,
+        icon_handle: IconHandle::NotLoaded,
+    })
+}
+
+impl Provider for RunProvider {
+    fn name(&self) -> &str {
+        "run"
+    }
+
+    fn load(&self) -> Listing {
+        (self.loader)()
+            .into_iter()
+            .map(app_item)
+            .collect::<Vec<_>>()
+            .into()
+    }
+
+    fn activate(&self, item: &Item) -> anyhow::Result<Activation> {
+        if let Some(command) = item.data::<AppDescriptor>() {
+            (self.launcher)(command)?;
+        }
+        Ok(Activation::Done)
+    }
+
+    // A query with arguments, or one that matches no executable, is run as typed
+    fn activate_query(
+        &self,
+        query: &str,
+        selected: Option<&Item>,
+    ) -> Option<anyhow::Result<Activation>> {
+        let command = query.trim();
+        let has_args = command.contains(char::is_whitespace);
+        if command.is_empty() || (!has_args && selected.is_some()) {
+            return None;
+        }
+
+        typed_command(command).map(|command| (self.launcher)(&command).map(|_| Activation::Done))
+    }
+}
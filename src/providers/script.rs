@@ -0,0 +1,284 @@
+//! Menus driven by an external command, following [rofi's script protocol](https://davatorium.github.io/rofi/current/rofi-script.5/).
+//!
+//! The command is run without arguments and lists one entry per line.  When the user picks an
+//! entry, the command is run again with the entry's text as its argument: its output replaces
+//! the list, and empty output closes the launcher.  A command that starts a program should do so
+//! in the background with its output redirected.  `ROFI_RETV` is 0 on the first run, 1 for a
+//! picked entry and 2 for entered text; `ROFI_INFO` and `ROFI_DATA` pass back values set by the
+//! previous run.
+//!
+//! Entries may carry options after a NUL byte, as key/value pairs separated by the unit separator
+//! `\x1f`, e.g. `Firefox\0icon\x1ffirefox\x1finfo\x1fweb`.  Entry options are `icon`, `info`,
+//! `meta` (extra search terms) and `nonselectable`.  A line starting with NUL sets an option for
+//! the whole list: `prompt`, `message` or `data`.
+use std::io::Read;
+use std::process::{exit, Command, Stdio};
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context};
+use elbey_cache::load_icon;
+
+use crate::provider::{Activation, Item, Listing, Provider};
+
+/// `ROFI_RETV` for the first run of the command
+const RETV_INITIAL: u8 = 0;
+/// `ROFI_RETV` when an entry was picked
+const RETV_SELECTED: u8 = 1;
+/// `ROFI_RETV` when text matching no entry was entered
+const RETV_CUSTOM: u8 = 2;
+
+/// Options of an entry needed to act on it.
+#[derive(Debug, Clone, Default)]
+struct ScriptEntry {
+    info: Option<String>,
+    nonselectable: bool,
+}
+
+/// Lists the entries printed by a command and re-runs it with the user's choice.
+#[derive(Debug)]
+pub struct ScriptProvider {
+    /// The program and its arguments
+    command: Vec<String>,
+    /// How long each run may take before it is abandoned
+    timeout: Duration,
+    /// The last `data` option set by the command, passed back as `ROFI_DATA`
+    data: Mutex<Option<String>>,
+}
+
+impl ScriptProvider {
+    pub fn new(command: &str, timeout: Duration) -> anyhow::Result<Self> {
+        let command = shell_words::split(command)?;
+        if command.is_empty() {
+            bail!("Empty script command");
+        }
+        Ok(ScriptProvider {
+            command,
+            timeout,
+            data: Mutex::new(None),
+        })
+    }
+
+    /// Run the command, returning the listing it printed, or `None` if it printed nothing.
+    fn run(
+        &self,
+        retv: u8,
+        argument: Option<&str>,
+        info: Option<&str>,
+    ) -> anyhow::Result<Option<Listing>> {
+        let mut command = Command::new(&self.command[0]);
+        command
+            .args(&self.command[1..])
+            .args(argument)
+            .env("ROFI_RETV", retv.to_string());
+        if let Some(info) = info {
+            command.env("ROFI_INFO", info);
+        }
+        if let Some(data) = self.data.lock().ok().and_then(|data| data.clone()) {
+            command.env("ROFI_DATA", data);
+        }
+
+        let (stdout, stderr) = run_with_timeout(command, self.timeout)
+            .with_context(|| format!("Script `{}` failed", self.command.join(" ")))?;
+        if stdout.trim().is_empty() {
+            return Ok(None);
+        }
+
+        let (mut listing, data) = parse_listing(&stdout);
+        if data.is_some() {
+            if let Ok(mut current) = self.data.lock() {
+                *current = data;
+            }
+        }
+        let stderr = stderr.trim();
+        if !stderr.is_empty() {
+            listing.message = Some(match listing.message {
+                Some(message) => format!("{message}\n{stderr}"),
+                None => stderr.to_string(),
+            });
+        }
+        Ok(Some(listing))
+    }
+
+    // Run the command for a choice; printing nothing means the menu is finished
+    fn choose(&self, retv: u8, argument: &str, info: Option<&str>) -> anyhow::Result<Activation> {
+        match self.run(retv, Some(argument), info)? {
+            Some(listing) => Ok(Activation::Replace(listing)),
+            None => exit(0),
+        }
+    }
+}
+
+/// Read a pipe to the end on a background thread.
+fn read_in_background(pipe: Option<impl Read + Send + 'static>) -> Receiver<String> {
+    let (sender, receiver) = channel();
+    if let Some(mut pipe) = pipe {
+        std::thread::spawn(move || {
+            let mut buffer = Vec::new();
+            let _ = pipe.read_to_end(&mut buffer);
+            let _ = sender.send(String::from_utf8_lossy(&buffer).into_owned());
+        });
+    }
+    receiver
+}
+
+/// Run `command` to completion and return its stdout and stderr, killing it after `timeout`.
+fn run_with_timeout(mut command: Command, timeout: Duration) -> anyhow::Result<(String, String)> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let stdout = read_in_background(child.stdout.take());
+    let stderr = read_in_background(child.stderr.take());
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            bail!("timed out after {:.1}s", timeout.as_secs_f32());
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    };
+
+    // A program started by the script may still hold the pipes open
+    let remaining = || deadline.saturating_duration_since(Instant::now());
+    let stdout = stdout
+        .recv_timeout(remaining())
+        .context("output was not closed; redirect the output of programs it starts")?;
+    let stderr = stderr.recv_timeout(remaining()).unwrap_or_default();
+
+    if !status.success() {
+        bail!("{status}: {}", stderr.trim());
+    }
+    Ok((stdout, stderr))
+}
+
+/// Parse the command's output into a listing, along with its `data` option.
+fn parse_listing(output: &str) -> (Listing, Option<String>) {
+    let mut listing = Listing::default();
+    let mut data = None;
+
+    for line in output.lines() {
+        if let Some(option) = line.strip_prefix('\0') {
+            let (key, value) = option.split_once('\x1f').unwrap_or((option, ""));
+            match key {
+                "prompt" => listing.prompt = Some(value.to_string()),
+                "message" => listing.message = Some(value.to_string()),
+                "data" => data = Some(value.to_string()),
+                _ => {}
+            }
+            continue;
+        }
+
+        let (text, options) = line.split_once('\0').unwrap_or((line, ""));
+        let mut item = Item::new(listing.items.len().to_string(), text);
+        let mut entry = ScriptEntry::default();
+        let fields: Vec<&str> = options.split('\x1f').collect();
+        for pair in fields.chunks(2) {
+            match pair {
+                ["icon", icon] => item = item.with_icon(load_icon(icon)),
+                ["info", info] => entry.info = Some(info.to_string()),
+                ["meta", meta] => item = item.with_keywords(meta.split_whitespace()),
+                ["nonselectable", value] => entry.nonselectable = *value == "true",
+                _ => {}
+            }
+        }
+        listing.items.push(item.with_data(entry));
+    }
+
+    (listing, data)
+}
+
+impl Provider for ScriptProvider {
+    fn name(&self) -> &str {
+        "script"
+    }
+
+    fn load(&self) -> Listing {
+        match self.run(RETV_INITIAL, None, None) {
+            Ok(listing) => listing.unwrap_or_default(),
+            Err(err) => Listing {
+                message: Some(format!("{err:#}")),
+                ..Listing::default()
+            },
+        }
+    }
+
+    fn activate(&self, item: &Item) -> anyhow::Result<Activation> {
+        let entry = item.data::<ScriptEntry>().cloned().unwrap_or_default();
+        if entry.nonselectable {
+            return Ok(Activation::Done);
+        }
+        self.choose(RETV_SELECTED, &item.title, entry.info.as_deref())
+    }
+
+    // Text that matches no entry is passed to the command as entered
+    fn activate_query(
+        &self,
+        query: &str,
+        selected: Option<&Item>,
+    ) -> Option<anyhow::Result<Activation>> {
+        if selected.is_some() || query.is_empty() {
+            return None;
+        }
+        Some(self.choose(RETV_CUSTOM, query, None))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_listing_options() {
+        let output = "\0prompt\x1fpick\n\0data\x1fstate-1\nFirefox\0icon\x1fno-such-icon\x1finfo\x1fweb\x1fmeta\x1fbrowser\n---\0nonselectable\x1ftrue\n";
+        let (listing, data) = parse_listing(output);
+
+        assert_eq!(listing.prompt.as_deref(), Some("pick"));
+        assert_eq!(data.as_deref(), Some("state-1"));
+        assert_eq!(listing.items.len(), 2);
+        assert_eq!(listing.items[0].title, "Firefox");
+        assert!(listing.items[0].matches("browser"));
+        let entry = listing.items[0].data::<ScriptEntry>().unwrap();
+        assert_eq!(entry.info.as_deref(), Some("web"));
+        assert!(
+            listing.items[1]
+                .data::<ScriptEntry>()
+                .unwrap()
+                .nonselectable
+        );
+    }
+
+    #[test]
+    fn test_script_is_rerun_with_selection() {
+        let script = r#"sh -c 'if [ "$ROFI_RETV" = 1 ]; then echo "picked $1"; echo warn >&2; else echo one; echo two; fi' menu"#;
+        let unit = ScriptProvider::new(script, Duration::from_secs(5)).unwrap();
+
+        let listing = unit.load();
+        assert_eq!(listing.items.len(), 2);
+
+        let Ok(Activation::Replace(listing)) = unit.activate(&listing.items[1]) else {
+            panic!("expected a new listing");
+        };
+        assert_eq!(listing.items[0].title, "picked two");
+        assert_eq!(listing.message.as_deref(), Some("warn"));
+    }
+
+    #[test]
+    fn test_script_timeout_and_failure_are_reported() {
+        let slow = ScriptProvider::new("sleep 5", Duration::from_millis(100)).unwrap();
+        let message = slow.load().message.unwrap();
+        assert!(message.contains("timed out"), "{message}");
+
+        let failing =
+            ScriptProvider::new("sh -c 'echo broken >&2; exit 3'", Duration::from_secs(5)).unwrap();
+        let message = failing.load().message.unwrap();
+        assert!(message.contains("broken"), "{message}");
+    }
+}
@@ -0,0 +1,269 @@
+//! Session and power actions: lock, logout, suspend, hibernate, reboot and shutdown.
+//!
+//! Each action runs a command that can be replaced with `--session-command <action>=<command>`.
+//! With confirmation enabled, the destructive actions ask again in the same window first.
+use anyhow::{bail, Context};
+use elbey_cache::load_icon;
+
+use crate::provider::{Activation, Item, Listing, Provider};
+use crate::providers::processes::current_uid;
+use crate::values::IconHandle;
+
+/// A session action and the command that performs it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionAction {
+    name: &'static str,
+    title: &'static str,
+    /// Themed icon names, tried in order
+    icons: &'static [&'static str],
+    command: Vec<String>,
+    /// Whether the action ends the session, so may need confirming
+    destructive: bool,
+}
+
+/// The actions in menu order: name, title, icons, default command and whether it is destructive.
+/// The logout command depends on the session, so is left empty here for [`session_actions`].
+const ACTIONS: &[(&str, &str, &[&str], &str, bool)] = &[
+    (
+        "lock",
+        "Lock",
+        &["system-lock-screen"],
+        "loginctl lock-session",
+        false,
+    ),
+    ("logout", "Log out", &["system-log-out"], "", true),
+    (
+        "suspend",
+        "Suspend",
+        &["system-suspend"],
+        "systemctl suspend",
+        false,
+    ),
+    (
+        "hibernate",
+        "Hibernate",
+        &["system-hibernate", "system-suspend-hibernate"],
+        "systemctl hibernate",
+        false,
+    ),
+    (
+        "reboot",
+        "Reboot",
+        &["system-reboot"],
+        "systemctl reboot",
+        true,
+    ),
+    (
+        "shutdown",
+        "Shut down",
+        &["system-shutdown"],
+        "systemctl poweroff",
+        true,
+    ),
+];
+
+/// End the session `session_id`, or all the sessions of the user `uid` when it is unknown.
+fn logout_command(
+    session_id: Option<String>,
+    uid: impl FnOnce() -> anyhow::Result<u32>,
+) -> anyhow::Result<Vec<String>> {
+    Ok(match session_id.filter(|id| !id.is_empty()) {
+        Some(id) => vec!["loginctl".into(), "terminate-session".into(), id],
+        None => vec![
+            "loginctl".into(),
+            "terminate-user".into(),
+            uid().context("Can't find the user to log out")?.to_string(),
+        ],
+    })
+}
+
+/// The session actions, with commands replaced by `overrides` of the form `<action>=<command>`.
+pub fn session_actions(overrides: &[String]) -> anyhow::Result<Vec<SessionAction>> {
+    let mut actions = ACTIONS
+        .iter()
+        .map(|(name, title, icons, command, destructive)| {
+            let command = match *name {
+                "logout" => logout_command(std::env::var("XDG_SESSION_ID").ok(), current_uid)?,
+                _ => shell_words::split(command)?,
+            };
+            Ok(SessionAction {
+                name,
+                title,
+                icons,
+                command,
+                destructive: *destructive,
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    for entry in overrides {
+        let (name, command) = entry
+            .split_once('=')
+            .with_context(|| format!("Expected <action>=<command>, got `{entry}`"))?;
+        let Some(action) = actions.iter_mut().find(|action| action.name == name.trim()) else {
+            bail!("Unknown session action `{name}`");
+        };
+        action.command = shell_words::split(command)?;
+        if action.command.is_empty() {
+            bail!("Empty command for session action `{name}`");
+        }
+    }
+    Ok(actions)
+}
+
+/// What choosing an item does.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Choice {
+    /// Run the action at this index, asking first if it needs confirming
+    Action(usize),
+    /// Run the action at this index, as confirmed
+    Confirmed(usize),
+    /// Go back to the list of actions
+    Cancel,
+}
+
+/// Lists session actions and runs the chosen one.
+#[derive(Debug)]
+pub struct SessionProvider {
+    actions: Vec<SessionAction>,
+    /// Whether destructive actions ask for confirmation
+    confirm: bool,
+    /// A function that runs a program and its arguments
+    launcher: fn(&[String]) -> anyhow::Result<()>,
+}
+
+impl SessionProvider {
+    pub fn new(
+        actions: Vec<SessionAction>,
+        confirm: bool,
+        launcher: fn(&[String]) -> anyhow::Result<()>,
+    ) -> Self {
+        SessionProvider {
+            actions,
+            confirm,
+            launcher,
+        }
+    }
+
+    fn action_item(&self, index: usize, choice: Choice) -> Item {
+        let action = &self.actions[index];
+        let icon = action
+            .icons
+            .iter()
+            .map(|icon| load_icon(icon))
+            .find(|icon| *icon != IconHandle::NotLoaded)
+            .unwrap_or(IconHandle::NotLoaded);
+        Item::new(action.name, action.title)
+            .with_icon(icon)
+            .with_keywords([action.name])
+            .with_data(choice)
+    }
+
+    /// Ask whether to run the action at `index`.
+    fn confirmation(&self, index: usize) -> Listing {
+        let action = &self.actions[index];
+        Listing {
+            items: vec![
+                self.action_item(index, Choice::Confirmed(index)),
+                Item::new("cancel", "Cancel").with_data(Choice::Cancel),
+            ],
+            message: Some(format!("{}?", action.title)),
+            prompt: Some("confirm".to_string()),
+        }
+    }
+}
+
+impl Provider for SessionProvider {
+    fn name(&self) -> &str {
+        "session"
+    }
+
+    fn load(&self) -> Listing {
+        (0..self.actions.len())
+            .map(|index| self.action_item(index, Choice::Action(index)))
+            .collect::<Vec<_>>()
+            .into()
+    }
+
+    fn activate(&self, item: &Item) -> anyhow::Result<Activation> {
+        let index = match item.data::<Choice>() {
+            Some(Choice::Action(index)) if self.confirm && self.actions[*index].destructive => {
+                return Ok(Activation::Replace(self.confirmation(*index)));
+            }
+            Some(Choice::Action(index) | Choice::Confirmed(index)) => *index,
+            Some(Choice::Cancel) => return Ok(Activation::Replace(self.load())),
+            None => return Ok(Activation::Done),
+        };
+        (self.launcher)(&self.actions[index].command)?;
+        Ok(Activation::Done)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ran(result: anyhow::Result<Activation>) -> String {
+        result.err().map(|err| err.to_string()).unwrap_or_default()
+    }
+
+    #[test]
+    fn test_session_commands_can_be_replaced() {
+        let actions = session_actions(&["lock=swaylock -f -c 000000".to_string()]).unwrap();
+        let unit = SessionProvider::new(actions, false, |args| anyhow::bail!("{}", args.join(" ")));
+        let items = unit.load().items;
+
+        assert_eq!(items.len(), 6);
+        assert_eq!(ran(unit.activate(&items[0])), "swaylock -f -c 000000");
+        assert_eq!(ran(unit.activate(&items[4])), "systemctl reboot");
+
+        assert_eq!(
+            unit.actions[1].command,
+            logout_command(std::env::var("XDG_SESSION_ID").ok(), current_uid).unwrap()
+        );
+
+        assert!(session_actions(&["dance=true".to_string()]).is_err());
+        assert!(session_actions(&["lock".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_logout_names_the_session_or_user() {
+        assert_eq!(
+            logout_command(Some("3".to_string()), || unreachable!()).unwrap(),
+            vec!["loginctl", "terminate-session", "3"]
+        );
+        assert_eq!(
+            logout_command(None, || Ok(1000)).unwrap(),
+            vec!["loginctl", "terminate-user", "1000"]
+        );
+        assert_eq!(
+            logout_command(Some(String::new()), || Ok(1000)).unwrap(),
+            vec!["loginctl", "terminate-user", "1000"]
+        );
+    }
+
+    #[test]
+    fn test_destructive_actions_are_confirmed() {
+        let unit = SessionProvider::new(session_actions(&[]).unwrap(), true, |args| {
+            anyhow::bail!("{}", args.join(" "))
+        });
+        let items = unit.load().items;
+
+        // Suspending needs no confirmation
+        assert_eq!(ran(unit.activate(&items[2])), "systemctl suspend");
+
+        let Ok(Activation::Replace(confirmation)) = unit.activate(&items[5]) else {
+            panic!("expected a confirmation");
+        };
+        assert_eq!(confirmation.message.as_deref(), Some("Shut down?"));
+        assert_eq!(
+            ran(unit.activate(&confirmation.items[0])),
+            "systemctl poweroff"
+        );
+
+        let Ok(Activation::Replace(listing)) = unit.activate(&confirmation.items[1]) else {
+            panic!("expected the actions again");
+        };
+        assert_eq!(listing.items.len(), 6);
+    }
+}
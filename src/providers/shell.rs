@@ -0,0 +1,111 @@
+//! The fallback that runs the typed query as a shell command, offered when nothing matches.
+use crate::provider::{Activation, Item, Listing, Provider};
+use crate::terminal::Terminal;
+
+/// Key of the history of commands run
+const HISTORY_MODE: &str = "shell";
+
+/// Offers to run the query through `$SHELL -c`, or in a terminal with Ctrl+Enter.
+#[derive(Debug)]
+pub struct ShellProvider {
+    terminal: Terminal,
+    /// A function that runs a program and its arguments
+    launcher: fn(&[String]) -> anyhow::Result<()>,
+    /// A function that returns the history of a mode, most recent first
+    history: fn(&str) -> Vec<String>,
+    /// A function that adds an entry to the history of a mode
+    record: fn(&str, &str),
+}
+
+impl ShellProvider {
+    pub fn new(
+        terminal: Terminal,
+        launcher: fn(&[String]) -> anyhow::Result<()>,
+        history: fn(&str) -> Vec<String>,
+        record: fn(&str, &str),
+    ) -> Self {
+        ShellProvider {
+            terminal,
+            launcher,
+            history,
+            record,
+        }
+    }
+
+    /// The command line that runs `command` with the user's shell.
+    fn shell_command(command: &str) -> Vec<String> {
+        let shell = std::env::var("SHELL")
+            .ok()
+            .filter(|shell| !shell.is_empty())
+            .unwrap_or_else(|| "/bin/sh".to_string());
+        vec![shell, "-c".to_string(), command.to_string()]
+    }
+
+    fn run(&self, item: &Item, in_terminal: bool) -> anyhow::Result<Activation> {
+        let command = Self::shell_command(&item.id);
+        (self.record)(HISTORY_MODE, &item.id);
+        if in_terminal {
+            (self.launcher)(&self.terminal.command(&command))?;
+        } else {
+            (self.launcher)(&command)?;
+        }
+        Ok(Activation::Done)
+    }
+}
+
+impl Provider for ShellProvider {
+    fn name(&self) -> &str {
+        HISTORY_MODE
+    }
+
+    fn load(&self) -> Listing {
+        Listing::default()
+    }
+
+    fn query(&self, query: &str) -> Vec<Item> {
+        let command = query.trim();
+        if command.is_empty() {
+            return Vec::new();
+        }
+        vec![Item::new(command, format!("Run: {command}"))
+            .with_subtitle("Enter runs it, Ctrl+Enter runs it in a terminal")]
+    }
+
+    fn activate(&self, item: &Item) -> anyhow::Result<Activation> {
+        self.run(item, false)
+    }
+
+    fn activate_alternate(&self, item: &Item) -> anyhow::Result<Activation> {
+        self.run(item, true)
+    }
+
+    fn history(&self) -> Vec<String> {
+        (self.history)(HISTORY_MODE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_runs_in_shell_or_terminal() {
+        std::env::set_var("SHELL", "/bin/bash");
+        let terminal = Terminal::new(Some("foot")).unwrap();
+        let unit = ShellProvider::new(
+            terminal,
+            |args| anyhow::bail!("{}", args.join(" ")),
+            |_| Vec::new(),
+            |_, _| {},
+        );
+
+        assert!(unit.query("  ").is_empty());
+        let items = unit.query(" make -j4 ");
+        assert_eq!(items[0].title, "Run: make -j4");
+
+        let ran = unit.activate(&items[0]).unwrap_err().to_string();
+        assert_eq!(ran, "/bin/bash -c make -j4");
+        let ran = unit.activate_alternate(&items[0]).unwrap_err().to_string();
+        assert_eq!(ran, "foot /bin/bash -c make -j4");
+    }
+}
@@ -0,0 +1,293 @@
+//! Hosts from `~/.ssh/config`, following `Include` directives, and from unhashed entries in
+//! `~/.ssh/known_hosts`, opened with `ssh` in a terminal.
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::provider::{Activation, Item, Listing, Provider};
+use crate::terminal::Terminal;
+
+/// Key of the usage counts of picked hosts
+const USAGE_MODE: &str = "ssh";
+
+/// How deeply `Include` directives are followed, as a guard against include cycles
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// A host to connect to, as named in the config or known hosts.
+#[derive(Debug, Clone, PartialEq)]
+struct SshHost {
+    name: String,
+    /// The `HostName` the config maps the name to
+    hostname: Option<String>,
+    /// The port of a `[host]:port` known hosts entry
+    port: Option<String>,
+}
+
+/// Lists ssh hosts, most used first, and connects to the chosen one in a terminal.
+#[derive(Debug)]
+pub struct SshProvider {
+    /// The directory holding `config` and `known_hosts`
+    ssh_dir: PathBuf,
+    terminal: Terminal,
+    /// A function that runs a program and its arguments
+    launcher: fn(&[String]) -> anyhow::Result<()>,
+    /// A function that returns the usage counts of a mode, keyed by item
+    usage: fn(&str) -> HashMap<String, usize>,
+    /// A function that increments the usage count of an item in a mode
+    record: fn(&str, &str),
+}
+
+impl SshProvider {
+    pub fn new(
+        ssh_dir: PathBuf,
+        terminal: Terminal,
+        launcher: fn(&[String]) -> anyhow::Result<()>,
+        usage: fn(&str) -> HashMap<String, usize>,
+        record: fn(&str, &str),
+    ) -> Self {
+        SshProvider {
+            ssh_dir,
+            terminal,
+            launcher,
+            usage,
+            record,
+        }
+    }
+}
+
+/// The default directory of ssh's user configuration.
+pub fn default_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| Path::new(&home).join(".ssh"))
+}
+
+fn is_pattern(name: &str) -> bool {
+    name.contains(['*', '?', '!'])
+}
+
+/// Split a config line into its keyword and arguments; `Keyword=value` is also accepted.
+fn config_line(line: &str) -> Option<(String, Vec<String>)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let (keyword, rest) = line
+        .split_once(|c: char| c.is_whitespace() || c == '=')
+        .unwrap_or((line, ""));
+    let rest = rest.trim_start().trim_start_matches('=');
+    let args = shell_words::split(rest)
+        .unwrap_or_else(|_| rest.split_whitespace().map(str::to_string).collect());
+    Some((keyword.to_lowercase(), args))
+}
+
+/// Collect the hosts named by `Host` lines of the config at `path` and the files it includes.
+fn config_hosts(ssh_dir: &Path, path: &Path, depth: usize, hosts: &mut Vec<SshHost>) {
+    let Ok(config) = std::fs::read_to_string(path) else {
+        return;
+    };
+    // Indices of the hosts named by the current `Host` line
+    let mut current = Vec::new();
+    for (keyword, args) in config.lines().filter_map(config_line) {
+        match keyword.as_str() {
+            "host" => {
+                current.clear();
+                for name in args.iter().filter(|name| !is_pattern(name)) {
+                    current.push(hosts.len());
+                    hosts.push(SshHost {
+                        name: name.clone(),
+                        hostname: None,
+                        port: None,
+                    });
+                }
+            }
+            "match" => current.clear(),
+            // As with ssh itself, the first value given for a host wins
+            "hostname" => {
+                for index in &current {
+                    let hostname = &mut hosts[*index].hostname;
+                    if hostname.is_none() {
+                        *hostname = args.first().cloned();
+                    }
+                }
+            }
+            "include" if depth < MAX_INCLUDE_DEPTH => {
+                for pattern in &args {
+                    let pattern = expand_include(ssh_dir, pattern);
+                    let Ok(paths) = glob::glob(&pattern.to_string_lossy()) else {
+                        continue;
+                    };
+                    for included in paths.filter_map(Result::ok) {
+                        config_hosts(ssh_dir, &included, depth + 1, hosts);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Resolve an `Include` path: `~` is the home directory and relative paths are in `ssh_dir`.
+fn expand_include(ssh_dir: &Path, pattern: &str) -> PathBuf {
+    if let Some(rest) = pattern.strip_prefix("~/") {
+        if let Some(home) = std::env::var_os("HOME") {
+            return Path::new(&home).join(rest);
+        }
+    }
+    ssh_dir.join(pattern)
+}
+
+/// Collect the first host named on each line of a known hosts file, skipping hashed entries.
+fn known_hosts(contents: &str) -> Vec<SshHost> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let mut names = fields.next()?;
+            // Lines marked `@cert-authority` or `@revoked` name hosts in the second field
+            if names.starts_with('@') {
+                names = fields.next()?;
+            }
+            if names.starts_with('#') || names.starts_with('|') {
+                return None;
+            }
+            let name = names.split(',').next()?;
+            if is_pattern(name) {
+                return None;
+            }
+            let (name, port) = match name
+                .strip_prefix('[')
+                .and_then(|rest| rest.split_once("]:"))
+            {
+                Some((name, port)) => (name, Some(port.to_string())),
+                None => (name, None),
+            };
+            Some(SshHost {
+                name: name.to_string(),
+                hostname: None,
+                port,
+            })
+        })
+        .collect()
+}
+
+/// The `ssh` command line that connects to `host`.
+fn ssh_command(host: &SshHost) -> Vec<String> {
+    let mut args = vec!["ssh".to_string()];
+    if let Some(port) = &host.port {
+        args.extend(["-p".to_string(), port.clone()]);
+    }
+    args.push(host.name.clone());
+    args
+}
+
+impl Provider for SshProvider {
+    fn name(&self) -> &str {
+        USAGE_MODE
+    }
+
+    fn load(&self) -> Listing {
+        let mut hosts = Vec::new();
+        config_hosts(&self.ssh_dir, &self.ssh_dir.join("config"), 0, &mut hosts);
+        if let Ok(contents) = std::fs::read_to_string(self.ssh_dir.join("known_hosts")) {
+            hosts.extend(known_hosts(&contents));
+        }
+
+        let counts = (self.usage)(USAGE_MODE);
+        let mut seen = HashSet::new();
+        let mut items: Vec<Item> = hosts
+            .into_iter()
+            .filter(|host| seen.insert(host.name.clone()))
+            .map(|host| {
+                let mut item = Item::new(host.name.clone(), host.name.clone())
+                    .with_rank(counts.get(&host.name).copied().unwrap_or(0));
+                if let Some(hostname) = &host.hostname {
+                    item = item
+                        .with_subtitle(hostname.clone())
+                        .with_keywords([hostname]);
+                }
+                item.with_data(host)
+            })
+            .collect();
+        // Stable, so hosts used equally often keep their config order
+        items.sort_by_key(|item| std::cmp::Reverse(item.rank));
+        items.into()
+    }
+
+    fn activate(&self, item: &Item) -> anyhow::Result<Activation> {
+        if let Some(host) = item.data::<SshHost>() {
+            (self.record)(USAGE_MODE, &host.name);
+            (self.launcher)(&self.terminal.command(&ssh_command(host)))?;
+        }
+        Ok(Activation::Done)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_ssh_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("elbey-ssh-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("config.d")).unwrap();
+        dir
+    }
+
+    fn no_usage(_mode: &str) -> HashMap<String, usize> {
+        HashMap::new()
+    }
+
+    fn db_used(_mode: &str) -> HashMap<String, usize> {
+        HashMap::from([("db".to_string(), 2)])
+    }
+
+    fn titles(provider: &SshProvider) -> Vec<String> {
+        provider
+            .load()
+            .items
+            .into_iter()
+            .map(|item| item.title)
+            .collect()
+    }
+
+    #[test]
+    fn test_hosts_from_config_includes_and_known_hosts() {
+        let dir = temp_ssh_dir("hosts");
+        std::fs::write(
+            dir.join("config"),
+            "Include config.d/*\n\nHost web web-alias\n  HostName web.example.com\n  HostName ignored.example.com\n\nHost *.internal !bastion\n  User admin\n\nHost=db\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("config.d/work"), "Host build\n  Port 2222\n").unwrap();
+        std::fs::write(
+            dir.join("known_hosts"),
+            "web,10.0.0.2 ssh-ed25519 AAAA\n|1|c2FsdA==|aGFzaA== ssh-ed25519 AAAA\n[git.example.com]:2200 ssh-rsa AAAA\n@cert-authority *.example.com ssh-rsa AAAA\n",
+        )
+        .unwrap();
+        let terminal = Terminal::new(Some("foot")).unwrap();
+
+        let unit = SshProvider::new(
+            dir.clone(),
+            terminal.clone(),
+            |_| Ok(()),
+            no_usage,
+            |_, _| {},
+        );
+        assert_eq!(
+            titles(&unit),
+            vec!["build", "web", "web-alias", "db", "git.example.com"]
+        );
+        let listing = unit.load();
+        assert_eq!(
+            listing.items[1].subtitle.as_deref(),
+            Some("web.example.com")
+        );
+        assert_eq!(
+            terminal.command(&ssh_command(listing.items[4].data::<SshHost>().unwrap())),
+            vec!["foot", "ssh", "-p", "2200", "git.example.com"]
+        );
+
+        let ranked = SshProvider::new(dir.clone(), terminal, |_| Ok(()), db_used, |_, _| {});
+        assert_eq!(titles(&ranked)[0], "db");
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
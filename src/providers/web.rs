@@ -0,0 +1,235 @@
+//! Web searches through prefix shortcuts ("bangs") such as `g rust iterators`, and links typed
+//! as bare URLs.
+use anyhow::{bail, Context};
+
+use crate::provider::{Activation, Item, Listing, Provider};
+
+/// A query prefix that expands into a URL.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bang {
+    prefix: String,
+    /// Shown in the result, e.g. `GitHub`
+    name: String,
+    /// The URL, with `{}` where the search terms go
+    template: String,
+}
+
+/// The built-in bangs: prefix, name and URL template.
+const BANGS: &[(&str, &str, &str)] = &[
+    ("g", "Google", "https://www.google.com/search?q={}"),
+    ("ddg", "DuckDuckGo", "https://duckduckgo.com/?q={}"),
+    ("gh", "GitHub", "https://github.com/{}"),
+    ("crates", "crates.io", "https://crates.io/search?q={}"),
+];
+
+/// The built-in bangs, followed by those added or replaced by `overrides` of the form
+/// `<prefix>=<url template>`.
+pub fn bangs(overrides: &[String]) -> anyhow::Result<Vec<Bang>> {
+    let mut bangs: Vec<Bang> = BANGS
+        .iter()
+        .map(|(prefix, name, template)| Bang {
+            prefix: prefix.to_string(),
+            name: name.to_string(),
+            template: template.to_string(),
+        })
+        .collect();
+
+    for entry in overrides {
+        let (prefix, template) = entry
+            .split_once('=')
+            .with_context(|| format!("Expected <prefix>=<url template>, got `{entry}`"))?;
+        let prefix = prefix.trim();
+        if prefix.is_empty() || prefix.contains(char::is_whitespace) {
+            bail!("Invalid bang prefix `{prefix}`");
+        }
+        if !template.contains("{}") {
+            bail!("The URL template for `{prefix}` has no {{}} for the search terms");
+        }
+        let bang = Bang {
+            prefix: prefix.to_string(),
+            name: prefix.to_string(),
+            template: template.to_string(),
+        };
+        match bangs.iter_mut().find(|existing| existing.prefix == prefix) {
+            Some(existing) => *existing = bang,
+            None => bangs.push(bang),
+        }
+    }
+    Ok(bangs)
+}
+
+/// Percent-encode search terms for a URL, leaving unreserved characters and `/` as they are so
+/// that paths such as `owner/repo` keep working.
+fn encode_terms(terms: &str) -> String {
+    let mut encoded = String::with_capacity(terms.len());
+    for byte in terms.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+/// Returns `query` as a URL if it is one: it must have an http(s) scheme or start with `www.`.
+fn bare_url(query: &str) -> Option<String> {
+    if query.is_empty() || query.contains(char::is_whitespace) {
+        return None;
+    }
+    let lower = query.to_lowercase();
+    if lower.starts_with("https://") || lower.starts_with("http://") {
+        Some(query.to_string())
+    } else if lower.starts_with("www.") && query.len() > 4 {
+        Some(format!("https://{query}"))
+    } else {
+        None
+    }
+}
+
+/// Offers to open the URL a query stands for.
+#[derive(Debug)]
+pub struct WebProvider {
+    bangs: Vec<Bang>,
+    /// The bang that searches for any other query, when used as a fallback
+    engine: Option<Bang>,
+    /// A function that opens a URL
+    opener: fn(&str) -> anyhow::Result<()>,
+}
+
+impl WebProvider {
+    pub fn new(bangs: Vec<Bang>, opener: fn(&str) -> anyhow::Result<()>) -> Self {
+        WebProvider {
+            bangs,
+            engine: None,
+            opener,
+        }
+    }
+
+    /// Search the whole query with the bang `prefix` when it has no bang of its own.
+    pub fn with_engine(mut self, prefix: &str) -> anyhow::Result<Self> {
+        let engine = self.bangs.iter().find(|bang| bang.prefix == prefix);
+        self.engine = Some(
+            engine
+                .cloned()
+                .with_context(|| format!("Unknown bang `{prefix}`"))?,
+        );
+        Ok(self)
+    }
+
+    fn search(bang: &Bang, terms: &str) -> (String, String) {
+        let url = bang.template.replace("{}", &encode_terms(terms));
+        (url, format!("Search {}: {terms}", bang.name))
+    }
+
+    /// The URL `query` expands to, with a description of it.
+    fn url(&self, query: &str) -> Option<(String, String)> {
+        let query = query.trim();
+        if let Some(url) = bare_url(query) {
+            return Some((url.clone(), format!("Open {url}")));
+        }
+        let bang = query
+            .split_once(char::is_whitespace)
+            .and_then(|(prefix, terms)| {
+                let bang = self.bangs.iter().find(|bang| bang.prefix == prefix)?;
+                Some((bang, terms.trim()))
+            });
+        match (bang, &self.engine) {
+            (Some((bang, terms)), _) if !terms.is_empty() => Some(Self::search(bang, terms)),
+            (_, Some(engine)) if !query.is_empty() => Some(Self::search(engine, query)),
+            _ => None,
+        }
+    }
+}
+
+impl Provider for WebProvider {
+    fn name(&self) -> &str {
+        "web"
+    }
+
+    fn load(&self) -> Listing {
+        Listing::default()
+    }
+
+    fn query(&self, query: &str) -> Vec<Item> {
+        self.url(query)
+            .map(|(url, title)| vec![Item::new(url.clone(), title).with_subtitle(url)])
+            .unwrap_or_default()
+    }
+
+    fn activate(&self, item: &Item) -> anyhow::Result<Activation> {
+        (self.opener)(&item.id)?;
+        Ok(Activation::Done)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn urls(provider: &WebProvider, query: &str) -> Vec<String> {
+        provider
+            .query(query)
+            .into_iter()
+            .map(|item| item.id)
+            .collect()
+    }
+
+    #[test]
+    fn test_bangs_expand_to_urls() {
+        let configured = bangs(&[
+            "w=https://en.wikipedia.org/wiki/Special:Search?search={}".to_string(),
+            "g=https://www.google.com/search?hl=en&q={}".to_string(),
+        ])
+        .unwrap();
+        let unit = WebProvider::new(configured, |_| Ok(()));
+
+        assert_eq!(
+            urls(&unit, "ddg rust & C++"),
+            vec!["https://duckduckgo.com/?q=rust%20%26%20C%2B%2B"]
+        );
+        assert_eq!(
+            urls(&unit, "gh rust-lang/rust"),
+            vec!["https://github.com/rust-lang/rust"]
+        );
+        assert_eq!(
+            urls(&unit, "g elbey"),
+            vec!["https://www.google.com/search?hl=en&q=elbey"]
+        );
+        assert_eq!(
+            urls(&unit, "w Linux"),
+            vec!["https://en.wikipedia.org/wiki/Special:Search?search=Linux"]
+        );
+        assert!(urls(&unit, "g").is_empty());
+        assert!(urls(&unit, "gimp image").is_empty());
+
+        let fallback = WebProvider::new(bangs(&[]).unwrap(), |_| Ok(()))
+            .with_engine("ddg")
+            .unwrap();
+        let items = fallback.query("gimp image");
+        assert_eq!(items[0].title, "Search DuckDuckGo: gimp image");
+        assert_eq!(items[0].id, "https://duckduckgo.com/?q=gimp%20image");
+        assert_eq!(
+            urls(&fallback, "gh elbey"),
+            vec!["https://github.com/elbey"]
+        );
+
+        assert!(bangs(&["x=https://example.com".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_bare_urls_open_directly() {
+        let unit = WebProvider::new(bangs(&[]).unwrap(), |_| Ok(()));
+
+        assert_eq!(
+            urls(&unit, "https://example.com/a?b=c"),
+            vec!["https://example.com/a?b=c"]
+        );
+        assert_eq!(
+            urls(&unit, "www.rust-lang.org"),
+            vec!["https://www.rust-lang.org"]
+        );
+        assert!(urls(&unit, "example.txt").is_empty());
+    }
+}
@@ -0,0 +1,146 @@
+//! Resolution of the directories scanned for desktop entries.
+use std::fmt::{self, Display, Formatter};
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use freedesktop_desktop_entry::default_paths;
+use glob::Pattern;
+
+/// Where a search path was configured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathOrigin {
+    /// Derived from `XDG_DATA_HOME` and `XDG_DATA_DIRS`.
+    Xdg,
+    /// Added by the user with `--extra-app-dir`.
+    Extra,
+}
+
+impl Display for PathOrigin {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            PathOrigin::Xdg => write!(f, "xdg"),
+            PathOrigin::Extra => write!(f, "extra"),
+        }
+    }
+}
+
+/// A directory scanned for desktop entries.
+#[derive(Debug, Clone)]
+pub struct SearchPath {
+    pub path: PathBuf,
+    pub origin: PathOrigin,
+}
+
+/// The effective set of directories to scan, along with exclusion patterns.
+#[derive(Debug, Clone)]
+pub struct SearchPaths {
+    dirs: Vec<SearchPath>,
+    excludes: Vec<Pattern>,
+}
+
+/// Expand a leading `~` to the user's home directory.
+fn expand_home(value: &str) -> String {
+    match (value.strip_prefix('~'), std::env::var("HOME")) {
+        (Some(rest), Ok(home)) if rest.is_empty() || rest.starts_with('/') => {
+            format!("{home}{rest}")
+        }
+        _ => value.to_string(),
+    }
+}
+
+impl SearchPaths {
+    /// Build the search paths from the XDG defaults followed by `extra_dirs`.  Entries in `excludes`
+    /// are glob patterns (or plain directories) matched against each directory and desktop file.
+    pub fn new(extra_dirs: &[String], excludes: &[String]) -> anyhow::Result<Self> {
+        let xdg = default_paths().map(|path| SearchPath {
+            path,
+            origin: PathOrigin::Xdg,
+        });
+        let extra = extra_dirs.iter().map(|dir| SearchPath {
+            path: PathBuf::from(expand_home(dir)),
+            origin: PathOrigin::Extra,
+        });
+
+        let excludes = excludes
+            .iter()
+            .map(|pattern| {
+                let expanded = expand_home(pattern);
+                Pattern::new(expanded.trim_end_matches('/'))
+                    .with_context(|| format!("Invalid exclude pattern: {pattern}"))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        Ok(SearchPaths {
+            dirs: xdg.chain(extra).collect(),
+            excludes,
+        })
+    }
+
+    /// All configured directories in priority order, including excluded ones.
+    pub fn dirs(&self) -> &[SearchPath] {
+        &self.dirs
+    }
+
+    /// Exclusion patterns as configured.
+    pub fn excludes(&self) -> &[Pattern] {
+        &self.excludes
+    }
+
+    /// Directories to scan in priority order, with excluded directories removed.
+    pub fn scan_dirs(&self) -> impl Iterator<Item = PathBuf> + '_ {
+        self.dirs
+            .iter()
+            .filter(|dir| !self.is_excluded(&dir.path))
+            .map(|dir| dir.path.clone())
+    }
+
+    /// Returns true if `path` or any of its parent directories matches an exclusion pattern.
+    pub fn is_excluded(&self, path: &Path) -> bool {
+        path.ancestors().any(|ancestor| {
+            self.excludes
+                .iter()
+                .any(|pattern| pattern.matches_path(ancestor))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extra_dirs_follow_xdg_dirs() {
+        let paths = SearchPaths::new(&["/opt/team/applications".to_string()], &[]).unwrap();
+        let last = paths.dirs().last().unwrap();
+
+        assert_eq!(last.origin, PathOrigin::Extra);
+        assert_eq!(last.path, PathBuf::from("/opt/team/applications"));
+        assert!(paths.dirs()[..paths.dirs().len() - 1]
+            .iter()
+            .all(|dir| dir.origin == PathOrigin::Xdg));
+    }
+
+    #[test]
+    fn test_exclude_directory_and_glob() {
+        let paths = SearchPaths::new(
+            &["/opt/team/applications".to_string()],
+            &["/opt/team/applications/".to_string(), "*/wine".to_string()],
+        )
+        .unwrap();
+
+        assert!(paths.is_excluded(Path::new("/opt/team/applications")));
+        assert!(paths.is_excluded(Path::new("/opt/team/applications/foo.desktop")));
+        assert!(paths.is_excluded(Path::new(
+            "/home/u/.local/share/applications/wine/Programs/notepad.desktop"
+        )));
+        assert!(!paths.is_excluded(Path::new("/usr/share/applications/firefox.desktop")));
+        assert!(!paths
+            .scan_dirs()
+            .any(|dir| dir == Path::new("/opt/team/applications")));
+    }
+
+    #[test]
+    fn test_invalid_exclude_pattern() {
+        assert!(SearchPaths::new(&[], &["[".to_string()]).is_err());
+    }
+}
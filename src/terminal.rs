@@ -0,0 +1,38 @@
+//! The terminal emulator used to run programs that need one, such as `ssh`.
+use anyhow::ensure;
+
+/// Used when neither `--terminal` nor `$TERMINAL` is set
+const DEFAULT_TERMINAL: &str = "xterm -e";
+
+/// A command line that runs a program in a new terminal window when the program and its arguments
+/// are appended, e.g. `foot` or `alacritty -e`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Terminal(Vec<String>);
+
+impl Terminal {
+    /// Parse the `--terminal` option, falling back to `$TERMINAL -e` and then `xterm -e`.
+    pub fn new(command: Option<&str>) -> anyhow::Result<Self> {
+        let args = match command {
+            Some(command) => shell_words::split(command)?,
+            None => match std::env::var("TERMINAL") {
+                Ok(terminal) if !terminal.trim().is_empty() => {
+                    let mut args = shell_words::split(&terminal)?;
+                    args.push("-e".to_string());
+                    args
+                }
+                _ => shell_words::split(DEFAULT_TERMINAL)?,
+            },
+        };
+        ensure!(!args.is_empty(), "Empty terminal command");
+        Ok(Terminal(args))
+    }
+
+    /// The command line that runs `args` in the terminal.
+    pub fn command<S: AsRef<str>>(&self, args: &[S]) -> Vec<String> {
+        self.0
+            .iter()
+            .cloned()
+            .chain(args.iter().map(|arg| arg.as_ref().to_string()))
+            .collect()
+    }
+}
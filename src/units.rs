@@ -0,0 +1,331 @@
+//! Offline conversion between units typed into the query, e.g. `10 km in mi`, `72 F to C`,
+//! `5 GiB in MB` or `3h20m in minutes`.
+//!
+//! A query is a quantity, `in` or `to`, and a target unit.  The quantity may add up several
+//! values of the same kind, as in `3h20m` or `5 ft 11 in`.  Symbols are matched exactly before
+//! ignoring case, so `Mb` (megabits) and `MB` (megabytes) differ, and a symbol shared by two kinds
+//! of unit, such as `m` for metres and minutes, takes the kind of the rest of the query.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Dimension {
+    Length,
+    Area,
+    Volume,
+    Mass,
+    Time,
+    Speed,
+    Temperature,
+    Data,
+}
+
+use Dimension::*;
+
+/// A unit as a scale and offset from the base unit of its dimension:
+/// `base = (value + offset) * factor`.
+#[derive(Debug)]
+struct Unit {
+    names: &'static [&'static str],
+    dimension: Dimension,
+    factor: f64,
+    offset: f64,
+}
+
+const fn unit(names: &'static [&'static str], dimension: Dimension, factor: f64) -> Unit {
+    Unit {
+        names,
+        dimension,
+        factor,
+        offset: 0.0,
+    }
+}
+
+/// Base units are metres, square metres, litres, grams, seconds, metres per second, kelvin and bytes.
+const UNITS: &[Unit] = &[
+    unit(
+        &[
+            "mm",
+            "millimeter",
+            "millimeters",
+            "millimetre",
+            "millimetres",
+        ],
+        Length,
+        0.001,
+    ),
+    unit(
+        &[
+            "cm",
+            "centimeter",
+            "centimeters",
+            "centimetre",
+            "centimetres",
+        ],
+        Length,
+        0.01,
+    ),
+    unit(&["m", "meter", "meters", "metre", "metres"], Length, 1.0),
+    unit(
+        &["km", "kilometer", "kilometers", "kilometre", "kilometres"],
+        Length,
+        1000.0,
+    ),
+    unit(&["in", "inch", "inches", "\""], Length, 0.0254),
+    unit(&["ft", "foot", "feet", "'"], Length, 0.3048),
+    unit(&["yd", "yard", "yards"], Length, 0.9144),
+    unit(&["mi", "mile", "miles"], Length, 1609.344),
+    unit(&["nmi", "nautical mile", "nautical miles"], Length, 1852.0),
+    unit(
+        &[
+            "m²",
+            "sqm",
+            "square meter",
+            "square meters",
+            "square metre",
+            "square metres",
+        ],
+        Area,
+        1.0,
+    ),
+    unit(
+        &[
+            "km²",
+            "square kilometer",
+            "square kilometers",
+            "square kilometre",
+            "square kilometres",
+        ],
+        Area,
+        1e6,
+    ),
+    unit(
+        &["ft²", "sqft", "square foot", "square feet"],
+        Area,
+        0.09290304,
+    ),
+    unit(&["ha", "hectare", "hectares"], Area, 1e4),
+    unit(&["ac", "acre", "acres"], Area, 4046.8564224),
+    unit(
+        &[
+            "ml",
+            "mL",
+            "milliliter",
+            "milliliters",
+            "millilitre",
+            "millilitres",
+        ],
+        Volume,
+        0.001,
+    ),
+    unit(
+        &["l", "L", "liter", "liters", "litre", "litres"],
+        Volume,
+        1.0,
+    ),
+    unit(
+        &[
+            "m³",
+            "cubic meter",
+            "cubic meters",
+            "cubic metre",
+            "cubic metres",
+        ],
+        Volume,
+        1000.0,
+    ),
+    unit(&["tsp", "teaspoon", "teaspoons"], Volume, 0.00492892159375),
+    unit(
+        &["tbsp", "tablespoon", "tablespoons"],
+        Volume,
+        0.01478676478125,
+    ),
+    unit(
+        &["floz", "fl oz", "fluid ounce", "fluid ounces"],
+        Volume,
+        0.0295735295625,
+    ),
+    unit(&["cup", "cups"], Volume, 0.2365882365),
+    unit(&["pt", "pint", "pints"], Volume, 0.473176473),
+    unit(&["qt", "quart", "quarts"], Volume, 0.946352946),
+    unit(&["gal", "gallon", "gallons"], Volume, 3.785411784),
+    unit(&["mg", "milligram", "milligrams"], Mass, 0.001),
+    unit(&["g", "gram", "grams"], Mass, 1.0),
+    unit(
+        &["kg", "kilogram", "kilograms", "kilo", "kilos"],
+        Mass,
+        1000.0,
+    ),
+    unit(&["t", "tonne", "tonnes"], Mass, 1e6),
+    unit(&["oz", "ounce", "ounces"], Mass, 28.349523125),
+    unit(&["lb", "lbs", "pound", "pounds"], Mass, 453.59237),
+    unit(&["st", "stone", "stones"], Mass, 6350.29318),
+    unit(&["ms", "millisecond", "milliseconds"], Time, 0.001),
+    unit(&["s", "sec", "secs", "second", "seconds"], Time, 1.0),
+    unit(&["m", "min", "mins", "minute", "minutes"], Time, 60.0),
+    unit(&["h", "hr", "hrs", "hour", "hours"], Time, 3600.0),
+    unit(&["d", "day", "days"], Time, 86400.0),
+    unit(&["wk", "week", "weeks"], Time, 604800.0),
+    unit(&["y", "yr", "yrs", "year", "years"], Time, 31557600.0),
+    unit(&["m/s", "mps"], Speed, 1.0),
+    unit(&["km/h", "kph", "kmh"], Speed, 1000.0 / 3600.0),
+    unit(&["mph", "mi/h"], Speed, 0.44704),
+    unit(&["kn", "kt", "knot", "knots"], Speed, 1852.0 / 3600.0),
+    unit(&["K", "kelvin"], Temperature, 1.0),
+    Unit {
+        names: &["C", "°C", "celsius"],
+        dimension: Temperature,
+        factor: 1.0,
+        offset: 273.15,
+    },
+    Unit {
+        names: &["F", "°F", "fahrenheit"],
+        dimension: Temperature,
+        factor: 5.0 / 9.0,
+        offset: 459.67,
+    },
+    unit(&["b", "bit", "bits"], Data, 0.125),
+    unit(&["B", "byte", "bytes"], Data, 1.0),
+    unit(&["kb", "Kb", "kbit", "kilobit", "kilobits"], Data, 125.0),
+    unit(&["Mb", "Mbit", "megabit", "megabits"], Data, 125e3),
+    unit(&["Gb", "Gbit", "gigabit", "gigabits"], Data, 125e6),
+    unit(&["kB", "KB", "kilobyte", "kilobytes"], Data, 1e3),
+    unit(&["MB", "megabyte", "megabytes"], Data, 1e6),
+    unit(&["GB", "gigabyte", "gigabytes"], Data, 1e9),
+    unit(&["TB", "terabyte", "terabytes"], Data, 1e12),
+    unit(&["PB", "petabyte", "petabytes"], Data, 1e15),
+    unit(&["KiB", "kibibyte", "kibibytes"], Data, 1024.0),
+    unit(&["MiB", "mebibyte", "mebibytes"], Data, 1048576.0),
+    unit(&["GiB", "gibibyte", "gibibytes"], Data, 1073741824.0),
+    unit(&["TiB", "tebibyte", "tebibytes"], Data, 1099511627776.0),
+    unit(&["PiB", "pebibyte", "pebibytes"], Data, 1125899906842624.0),
+];
+
+/// The units `name` may refer to, matching exactly if possible and otherwise ignoring case.
+fn lookup(name: &str) -> Vec<&'static Unit> {
+    let exact: Vec<_> = UNITS
+        .iter()
+        .filter(|unit| unit.names.contains(&name))
+        .collect();
+    if !exact.is_empty() {
+        return exact;
+    }
+    UNITS
+        .iter()
+        .filter(|unit| unit.names.iter().any(|n| n.eq_ignore_ascii_case(name)))
+        .collect()
+}
+
+/// Split a quantity such as `3h20m` or `5 ft 11 in` into its values and unit names.
+fn parse_quantity(text: &str) -> Option<Vec<(f64, &str)>> {
+    let mut parts = Vec::new();
+    let mut rest = text.trim();
+    while !rest.is_empty() {
+        let number_end = rest
+            .char_indices()
+            .find(|(i, c)| !(c.is_ascii_digit() || *c == '.' || (*i == 0 && *c == '-')))
+            .map_or(rest.len(), |(i, _)| i);
+        let value: f64 = rest[..number_end].parse().ok()?;
+        rest = rest[number_end..].trim_start();
+
+        // The unit runs to the next number, which may follow without a space
+        let unit_end = rest
+            .char_indices()
+            .find(|(_, c)| c.is_ascii_digit() || *c == '-')
+            .map_or(rest.len(), |(i, _)| i);
+        let unit = rest[..unit_end].trim();
+        if unit.is_empty() {
+            return None;
+        }
+        parts.push((value, unit));
+        rest = rest[unit_end..].trim_start();
+    }
+    (!parts.is_empty()).then_some(parts)
+}
+
+/// Convert `query` if it asks for a unit conversion, returning the value and the target unit as
+/// typed.
+pub fn convert(query: &str) -> Option<(f64, &str)> {
+    let query = query.trim();
+    let lower = query.to_ascii_lowercase();
+    // Split on the last separator, as `in` is also the symbol for inches
+    let separator = [" in ", " to "]
+        .iter()
+        .filter_map(|separator| lower.rfind(separator))
+        .max()?;
+    let target_name = query[separator + 4..].trim();
+    let parts = parse_quantity(&query[..separator])?;
+
+    let targets = lookup(target_name);
+    let sources = parts
+        .iter()
+        .map(|(value, name)| {
+            let units = lookup(name);
+            (!units.is_empty()).then_some((*value, units))
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    // The first kind of unit shared by the target and every part of the quantity
+    let target = targets.iter().find(|target| {
+        sources
+            .iter()
+            .all(|(_, units)| units.iter().any(|unit| unit.dimension == target.dimension))
+    })?;
+    let compound = sources.len() > 1;
+    if compound && target.dimension == Temperature {
+        return None;
+    }
+
+    let mut base = 0.0;
+    for (value, units) in &sources {
+        let unit = units
+            .iter()
+            .find(|unit| unit.dimension == target.dimension)?;
+        base += (value + unit.offset) * unit.factor;
+    }
+    let value = base / target.factor - target.offset;
+    value.is_finite().then_some((value, target_name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calc::format_number;
+
+    fn conversion(query: &str) -> Option<String> {
+        convert(query).map(|(value, unit)| format!("{} {unit}", format_number(value)))
+    }
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(
+            conversion("10 km in mi").as_deref(),
+            Some("6.21371192237 mi")
+        );
+        assert_eq!(conversion("72 F to C").as_deref(), Some("22.2222222222 C"));
+        assert_eq!(
+            conversion("-40 celsius to fahrenheit").as_deref(),
+            Some("-40 fahrenheit")
+        );
+        assert_eq!(conversion("0 C in K").as_deref(), Some("273.15 K"));
+        assert_eq!(conversion("5 GiB in MB").as_deref(), Some("5368.70912 MB"));
+        assert_eq!(conversion("100 Mb to MB").as_deref(), Some("12.5 MB"));
+        assert_eq!(
+            conversion("3h20m in minutes").as_deref(),
+            Some("200 minutes")
+        );
+        assert_eq!(conversion("5 ft 11 in in cm").as_deref(), Some("180.34 cm"));
+        assert_eq!(
+            conversion("20 m in ft").as_deref(),
+            Some("65.6167979003 ft")
+        );
+        assert_eq!(conversion("2 Kg to LB").as_deref(), Some("4.4092452437 LB"));
+    }
+
+    #[test]
+    fn test_queries_that_are_not_conversions() {
+        assert_eq!(conversion("10 km in kg"), None);
+        assert_eq!(conversion("how to cook"), None);
+        assert_eq!(conversion("10 parsecs in km"), None);
+        assert_eq!(conversion("km in mi"), None);
+        assert_eq!(conversion("10 C 5 F in K"), None);
+    }
+}
@@ -1,5 +1,6 @@
 //! Constants and literal values used throughout the application.
 use std::sync::LazyLock;
+use std::time::Duration;
 
 use iced::widget::Id;
 use iced::Theme;
@@ -12,9 +13,18 @@ pub const DEFAULT_WINDOW_WIDTH: u32 = 320;
 pub const DEFAULT_THEME: Theme = Theme::Nord;
 pub const DEFAULT_TEXT_SIZE: u16 = 16;
 pub const DEFAULT_HINT: &str = "drun";
+pub const DEFAULT_MODE: &str = "drun";
+pub const DEFAULT_FALLBACKS: &str = "run";
+pub const DEFAULT_SCRIPT_TIMEOUT: Duration = Duration::from_secs(10);
 
 pub static ENTRY_WIDGET_ID: LazyLock<Id> = LazyLock::new(|| Id::new("entry"));
 pub static ITEMS_WIDGET_ID: LazyLock<Id> = LazyLock::new(|| Id::new("items"));
 
 // The max number of items to render in the list
 pub const VIEWABLE_LIST_ITEM_COUNT: usize = 10;
+
+// Shown at the end of the rows of pinned items
+pub const PIN_MARKER: &str = "📌";
+
+// Shown above the list while a provider acts on a choice
+pub const BUSY_MESSAGE: &str = "Working…";